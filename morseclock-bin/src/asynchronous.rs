@@ -0,0 +1,314 @@
+//! An async display loop on [`tokio`], as an alternative to the blocking [`run`](crate::run)
+
+use crate::{identification, time, Led, Options, Pause, TimeSource, Timing};
+use chrono::NaiveDateTime;
+use morseclock::{BreakKind, Symbol};
+use std::future::Future;
+use std::iter;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// A LED which can be switched on and off without blocking
+pub trait AsyncLed: Send {
+    /// Switches the LED on
+    fn on(&mut self) -> impl Future<Output = anyhow::Result<()>> + Send;
+    /// Switches the LED off
+    fn off(&mut self) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Displays a single symbol, see [`Led::display`]
+    fn display(
+        &mut self,
+        symbol: Symbol,
+        on: Duration,
+        off: Duration,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send {
+        async move {
+            if matches!(symbol, Symbol::Short | Symbol::Long) {
+                self.on().await?;
+                sleep(on).await;
+                self.off().await?;
+            }
+
+            sleep(off).await;
+
+            Ok(())
+        }
+    }
+
+    /// Called before the time `now` is displayed, see [`Led::start_cycle`]
+    fn start_cycle(
+        &mut self,
+        _now: NaiveDateTime,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Called after a time has been displayed, see [`Led::end_cycle`]
+    fn end_cycle(&mut self) -> impl Future<Output = anyhow::Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+/// An [`AsyncLed`] switching a [`Led`] which doesn't block for long, e.g. a [`SysfsLed`](crate::SysfsLed)
+///
+/// Every call is forwarded to the wrapped LED. As [`Led::display`] sleeps for the
+/// duration of the symbol, the displaying task blocks its thread while a symbol is shown.
+#[derive(Debug)]
+pub struct Blocking<L>(pub L);
+
+impl<L: Led + Send> AsyncLed for Blocking<L> {
+    async fn on(&mut self) -> anyhow::Result<()> {
+        self.0.on()
+    }
+
+    async fn off(&mut self) -> anyhow::Result<()> {
+        self.0.off()
+    }
+
+    async fn display(&mut self, symbol: Symbol, on: Duration, off: Duration) -> anyhow::Result<()> {
+        self.0.display(symbol, on, off)
+    }
+
+    async fn start_cycle(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
+        self.0.start_cycle(now)
+    }
+
+    async fn end_cycle(&mut self) -> anyhow::Result<()> {
+        self.0.end_cycle()
+    }
+}
+
+async fn display<L, I>(led: &mut L, timing: &Timing, symbols: I) -> anyhow::Result<()>
+where
+    L: AsyncLed,
+    I: IntoIterator<Item = Symbol>,
+{
+    for symbol in symbols {
+        let (on, off) = timing.durations(symbol);
+        led.display(symbol, on, off).await?;
+    }
+
+    Ok(())
+}
+
+/// Displays the time of `time` on `led` according to `options` forever
+///
+/// Like [`run_with`](crate::run_with), but every sleep yields to the runtime. The loop
+/// is stopped by dropping the future, which happens immediately even during a long
+/// pause, see [`run_until_ctrl_c`]. [`Options::hold`], [`Options::on_no_time`],
+/// [`Options::max_cycle_duration`] and [`Options::once`] aren't supported.
+pub async fn run<L: AsyncLed, T: TimeSource>(
+    led: &mut L,
+    timing: &Timing,
+    options: &Options,
+    time: &T,
+) -> anyhow::Result<()> {
+    if let Some(id) = options.id {
+        let symbols = identification(id)
+            .into_iter()
+            .chain(iter::repeat_n(Symbol::Break(BreakKind::Group), 7));
+
+        display(led, timing, symbols).await?;
+    }
+
+    loop {
+        let now = time.now();
+        led.start_cycle(now).await?;
+        for repeat in 0..options.repeat.max(1) {
+            if repeat > 0 {
+                sleep(options.repeat_gap).await;
+            }
+
+            display(led, timing, crate::cycle_symbols(now, options)?).await?;
+        }
+        led.end_cycle().await?;
+
+        if let Some(status) = &options.status {
+            status.cycles.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let pause = match timing.pause {
+            Pause::Fixed(pause) => pause,
+            Pause::NextMinute => time::until_next_minute(now, time.now()),
+            Pause::Aligned(period) => time::until_next_boundary(now, time.now(), period),
+        };
+
+        sleep(pause).await;
+    }
+}
+
+/// Like [`run`], until Ctrl-C is pressed, then switches the LED off
+pub async fn run_until_ctrl_c<L: AsyncLed, T: TimeSource>(
+    led: &mut L,
+    timing: &Timing,
+    options: &Options,
+    time: &T,
+) -> anyhow::Result<()> {
+    tokio::select! {
+        result = run(led, timing, options, time) => result?,
+        result = tokio::signal::ctrl_c() => result?,
+    }
+
+    led.off().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DutyCycle, FixedTime};
+    use chrono::NaiveDate;
+    use morseclock::Format;
+    use tokio::time::Instant;
+
+    /// A LED which records every state it was switched to, with the time since `start`
+    struct MockLed {
+        start: Instant,
+        states: Vec<(bool, Duration)>,
+    }
+
+    impl AsyncLed for MockLed {
+        async fn on(&mut self) -> anyhow::Result<()> {
+            self.states.push((true, self.start.elapsed()));
+            Ok(())
+        }
+
+        async fn off(&mut self) -> anyhow::Result<()> {
+            self.states.push((false, self.start.elapsed()));
+            Ok(())
+        }
+    }
+
+    fn time(hour: u32, minute: u32) -> FixedTime {
+        FixedTime(
+            NaiveDate::from_ymd_opt(2024, 7, 4)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sleep_durations() {
+        let timing = Timing::new(
+            100,
+            Pause::Fixed(Duration::from_secs(10)),
+            DutyCycle(0.2),
+            DutyCycle(0.6),
+        );
+        let mut led = MockLed {
+            start: Instant::now(),
+            states: Vec::new(),
+        };
+        let options = Options::new(Format::Hour12);
+        let time = time(1, 0);
+        let ms = Duration::from_millis;
+
+        // "-.=-", the second cycle starts after the pause
+        tokio::select! {
+            result = run(&mut led, &timing, &options, &time) => result.unwrap(),
+            _ = sleep(ms(10_450)) => {}
+        }
+
+        assert_eq!(
+            led.states,
+            [
+                (true, ms(0)),
+                (false, ms(60)),
+                (true, ms(100)),
+                (false, ms(120)),
+                (true, ms(300)),
+                (false, ms(360)),
+                (true, ms(10_400)),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancel_pause() {
+        let timing = Timing::new(
+            100,
+            Pause::Fixed(Duration::from_secs(3600)),
+            DutyCycle(0.2),
+            DutyCycle(0.6),
+        );
+        let mut led = MockLed {
+            start: Instant::now(),
+            states: Vec::new(),
+        };
+        let options = Options::new(Format::Hour12);
+        let time = time(1, 0);
+        let start = Instant::now();
+
+        tokio::select! {
+            result = run(&mut led, &timing, &options, &time) => result.unwrap(),
+            _ = sleep(Duration::from_secs(1)) => {}
+        }
+
+        // cancelled in the middle of the pause
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert_eq!(led.states.len(), 6);
+    }
+
+    /// A LED which records the calls of a cycle
+    #[derive(Default)]
+    struct CycleLed(Vec<String>);
+
+    impl Led for CycleLed {
+        fn on(&mut self) -> anyhow::Result<()> {
+            self.0.push("on".into());
+            Ok(())
+        }
+
+        fn off(&mut self) -> anyhow::Result<()> {
+            self.0.push("off".into());
+            Ok(())
+        }
+
+        fn display(&mut self, symbol: Symbol, _on: Duration, _off: Duration) -> anyhow::Result<()> {
+            self.0.push(format!("{:?}", symbol));
+            Ok(())
+        }
+
+        fn start_cycle(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
+            self.0.push(now.format("start %H:%M").to_string());
+            Ok(())
+        }
+
+        fn end_cycle(&mut self) -> anyhow::Result<()> {
+            self.0.push("end".into());
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blocking_forwards() {
+        let timing = Timing::new(
+            100,
+            Pause::Fixed(Duration::from_secs(3600)),
+            DutyCycle(0.2),
+            DutyCycle(0.6),
+        );
+        let mut led = Blocking(CycleLed::default());
+        let options = Options::new(Format::Hour12);
+        let time = time(1, 0);
+
+        tokio::select! {
+            result = run(&mut led, &timing, &options, &time) => result.unwrap(),
+            _ = sleep(Duration::from_secs(1)) => {}
+        }
+
+        // "-.=-", displayed by the wrapped LED itself
+        assert_eq!(
+            led.0 .0,
+            [
+                "start 01:00",
+                "Long",
+                "Short",
+                "Break(Group)",
+                "Long",
+                "end"
+            ]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Audio rendering of symbols as morse tones
+
+use crate::Timing;
+use morseclock::Symbol;
+use std::f32::consts::TAU;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// The duration of the fade in and out of a tone, avoiding clicks at its edges
+const FADE: Duration = Duration::from_millis(5);
+
+/// The amplitude of a tone, leaving some headroom
+const AMPLITUDE: f32 = 0.5 * i16::MAX as f32;
+
+/// The number of samples lasting for `duration`
+fn sample_count(duration: Duration, sample_rate: u32) -> usize {
+    (duration.as_secs_f64() * f64::from(sample_rate)).round() as usize
+}
+
+/// Renders `symbols` as mono PCM samples of a sine tone with the frequency `freq_hz`
+///
+/// Shorts and longs are a tone for their on and silence for their off duration, breaks
+/// are silent. The durations are the ones used to blink a LED with `timing`, without
+/// a pause. See [`write_wav`] to write the samples to a WAV file.
+pub fn render_wav<I: IntoIterator<Item = Symbol>>(
+    symbols: I,
+    timing: &Timing,
+    freq_hz: f32,
+    sample_rate: u32,
+) -> Vec<i16> {
+    let step = TAU * freq_hz / sample_rate as f32;
+    let mut samples = Vec::new();
+
+    for symbol in symbols {
+        let (on, off) = timing.durations(symbol);
+        let tone = match symbol {
+            Symbol::Short | Symbol::Long => sample_count(on, sample_rate),
+            _ => 0,
+        };
+        let silence = sample_count(on + off, sample_rate) - tone;
+        let fade = sample_count(FADE, sample_rate).min(tone / 2).max(1);
+
+        samples.extend((0..tone).map(|i| {
+            let envelope = (i.min(tone - 1 - i) as f32 / fade as f32).min(1.0);
+            (AMPLITUDE * envelope * (step * i as f32).sin()) as i16
+        }));
+        samples.extend(std::iter::repeat_n(0, silence));
+    }
+
+    samples
+}
+
+/// Writes `samples` rendered by [`render_wav`] as a mono 16 bit PCM WAV file
+pub fn write_wav<W: Write>(mut writer: W, samples: &[i16], sample_rate: u32) -> io::Result<()> {
+    let data_len = u32::try_from(samples.len() * 2)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many samples"))?;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_len).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    // the format chunk: PCM, mono, 16 bits per sample
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * 2).to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DutyCycle, Pause};
+    use morseclock::{Clock, Format};
+
+    #[test]
+    fn render() {
+        let timing = Timing::new(100, Pause::NextMinute, DutyCycle(0.2), DutyCycle(0.6));
+        let clock = Clock::new(
+            4.try_into().unwrap(),
+            45.try_into().unwrap(),
+            Format::Hour12,
+        );
+        let samples = render_wav(clock, &timing, 800.0, 8000);
+
+        assert_eq!(
+            samples.len(),
+            sample_count(timing.display_duration(clock), 8000)
+        );
+
+        // "--.=----": the group break follows the short, after 100 + 100 + 20 ms on
+        let break_start = sample_count(Duration::from_millis(220), 8000);
+        let break_end = sample_count(Duration::from_millis(400), 8000);
+        assert!(samples[break_start..break_end].iter().all(|&s| s == 0));
+
+        // the first long is audible and fades in
+        let long = &samples[..sample_count(Duration::from_millis(60), 8000)];
+        assert!(long.iter().any(|&s| s.unsigned_abs() > 8000));
+        // at most a tenth of the amplitude after 4 of the 40 samples of the fade
+        assert!(long[..4].iter().all(|&s| s.unsigned_abs() < 1700));
+    }
+
+    #[test]
+    fn wav_header() {
+        let mut wav = Vec::new();
+        write_wav(&mut wav, &[0, 1, -1], 8000).unwrap();
+
+        assert_eq!(wav.len(), 44 + 6);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[4..8], &42u32.to_le_bytes());
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(&wav[24..28], &8000u32.to_le_bytes());
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(&wav[40..44], &6u32.to_le_bytes());
+        assert_eq!(&wav[44..], [0, 0, 1, 0, 0xff, 0xff]);
+    }
+}
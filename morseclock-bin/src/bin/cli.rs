@@ -1,16 +1,32 @@
-use chrono::{offset::Local, Timelike};
+use chrono::{NaiveTime, Timelike};
 use morseclock::{Clock, Format, MorseExt};
+use morseclock_bin::{config, TimeSource, Zone};
 
-fn app() -> Result<(), morseclock::Error> {
-    let now = Local::now();
+/// The hour and minute of `time`, or of the current time in `zone` if it isn't given
+fn hour_minute(time: Option<NaiveTime>, zone: &Zone) -> (u32, u32) {
+    let time = time.unwrap_or_else(|| zone.now().time());
 
-    let hour = now.hour().try_into()?;
-    let minute = now.minute().try_into()?;
+    (time.hour(), time.minute())
+}
+
+/// Renders the time selected by `args` as morse
+fn render(mut args: pico_args::Arguments) -> anyhow::Result<String> {
+    let zone: Zone = args.opt_value_from_str("--timezone")?.unwrap_or_default();
+    let format = args
+        .opt_value_from_fn("--format", config::parse_format)?
+        .unwrap_or(Format::Hour12);
+    let time = args.opt_value_from_fn("--time", |s| NaiveTime::parse_from_str(s, "%H:%M"))?;
 
-    let time: String = Clock::new(hour, minute, Format::Hour12)
+    let (hour, minute) = hour_minute(time, &zone);
+
+    Ok(Clock::new(hour.try_into()?, minute.try_into()?, format)
         .into_iter()
         .morse()
-        .collect();
+        .collect())
+}
+
+fn app() -> anyhow::Result<()> {
+    let time = render(pico_args::Arguments::from_env())?;
 
     println!("{}", time);
 
@@ -23,3 +39,32 @@ fn main() {
         std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    fn render(argv: &[&str]) -> anyhow::Result<String> {
+        super::render(pico_args::Arguments::from_vec(
+            argv.iter().map(OsString::from).collect(),
+        ))
+    }
+
+    #[test]
+    fn fixed_time() {
+        assert_eq!(
+            render(&["--time", "23:59", "--format", "24"]).unwrap(),
+            "--------..=----.."
+        );
+        assert_eq!(render(&["--time", "23:59"]).unwrap(), "----..=----..");
+        assert_eq!(render(&["--time", "00:00"]).unwrap(), "-=-");
+    }
+
+    #[test]
+    fn invalid_time() {
+        for time in ["24:00", "12:60", "12", "noon", "12:00:00"] {
+            assert!(render(&["--time", time]).is_err(), "{}", time);
+        }
+        assert!(render(&["--time", "12:00", "--format", "13"]).is_err());
+    }
+}
@@ -1,340 +1,830 @@
-use chrono::{offset::Local, Timelike};
-use morseclock::{Clock, Format, Symbol};
+use morseclock::{BreakKind, Format, Symbol, SymbolSequence};
+use morseclock_bin::notify::Watchdog;
+use morseclock_bin::{config, stream};
+use morseclock_bin::{
+    AmPmLed, BreakState, Config, DutyCycle, FifoLed, Hold, Inspect, Led, Options, Ratio, Rgb,
+    RgbSysfsLed, SimLed, Status, SysfsLed, Timing, Zone,
+};
+use signal_hook::consts::{SIGUSR1, SIGUSR2};
+use signal_hook::iterator::Signals;
 use std::convert::Infallible;
-use std::error;
 use std::ffi::OsString;
-use std::fmt;
-use std::fs;
-use std::io::{self, Seek, Write};
-use std::num;
-use std::path;
+use std::io;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
 use std::process;
-use std::str;
 use std::sync::{self, atomic};
 use std::thread;
 use std::time::Duration;
 
-pub mod parser {
-    use nom::bytes::complete::tag;
-    use nom::error::{ErrorKind, ParseError};
-    use nom::sequence::delimited;
-    use nom::{AsChar, Finish, IResult, InputTakeAtPosition};
+#[derive(Debug)]
+pub struct Args {
+    pub timing: Timing,
+    pub options: Options,
+    pub zone: Zone,
+    pub min_write_interval: Duration,
+    pub proportional_restore: bool,
+    pub newline: bool,
+    pub on_level: Option<u32>,
+    pub break_state: BreakState,
+    pub duty_brightness: bool,
+    pub idle_pulse: Option<DutyCycle>,
+    pub short_color: Option<Rgb>,
+    pub long_color: Option<Rgb>,
+    pub ampm_led: Option<OsString>,
+    pub user: Option<OsString>,
+    pub group: Option<OsString>,
+    pub chroot: Option<OsString>,
+    pub wait_writable: Option<Duration>,
+    pub print_durations: bool,
+    pub dry_run: bool,
+    pub verbose: bool,
+    pub pattern: Option<SymbolSequence>,
+    pub listen: Option<OsString>,
+    pub output: Output,
+}
 
-    fn trigger_char1<T, E: ParseError<T>>(input: T) -> IResult<T, T, E>
-    where
-        T: InputTakeAtPosition,
-        <T as InputTakeAtPosition>::Item: nom::AsChar,
-    {
-        input.split_at_position1(
-            |item| !matches!(item.as_char(), '0'..='9' | 'a'..='z' | 'A'..='Z' | '-'),
-            ErrorKind::AlphaNumeric,
-        )
-    }
+/// The output driven by the display loop
+#[derive(Debug)]
+pub enum Output {
+    Sysfs(OsString),
+    Fifo(OsString),
+    Simulate,
+    /// The LEDs displaying the hour and the other hands at the same time
+    Split(OsString, OsString),
+    /// The red, green and blue LEDs of a RGB LED
+    Rgb([OsString; 3]),
+}
 
-    pub fn parse_trigger(input: &str) -> Option<&str> {
-        let pos = input.find('[')?;
-        let input = &input[pos..];
+fn help() {
+    println!(
+        r#"
+morseclock-hw - Yet another not-so-useful LED clock
 
-        let trigger: Result<_, ()> = delimited(tag("["), trigger_char1, tag("]"))(input)
-            .finish()
-            .map(|(_, trigger)| match trigger {
-                "none" => None,
-                trigger => Some(trigger),
-            });
+Usage: morseclock-hw [PARAMS] [OPTIONS] LED_SYSFS_DIR
+       morseclock-hw [PARAMS] [OPTIONS] --fifo PATH
+       morseclock-hw [PARAMS] [OPTIONS] --simulate
+       morseclock-hw [PARAMS] [OPTIONS] --split-leds DIR_A DIR_B
+       morseclock-hw [PARAMS] [OPTIONS] --rgb RED_DIR,GREEN_DIR,BLUE_DIR
+       morseclock-hw --config FILE [PARAMS] [OPTIONS] [LED_SYSFS_DIR]
+       morseclock-hw --diagnose LED_SYSFS_DIR
+
+Parameters:
+    -p, --pause-duration    Duration of pause between two displayed times,
+                            "auto" to pause until the next minute or e.g.
+                            "align:30s" to pause until the next full or half minute
+    -b, --base-duration     Base duration of a blink
+    -l, --long-duty         Duty cycle of the long blink
+    -s, --short-duration    Duty cycle of the short blink
+    -r, --ratio             Ratio of the short and long blink, e.g. "1:3",
+                            instead of the duty cycles
+        --speed             Multiplier for all durations, e.g. 0.5 is twice as fast
+        --max-cycle-duration
+                            Skip displaying times which would take longer than
+                            the given duration, e.g. due to a misconfiguration
+        --no-pauses         Collapse all breaks and pauses to the given duration,
+                            displaying the times back to back (hard to read)
+        --repeat-gap        Duration of the gap between two repetitions of a time
+                            (default: three times the break between two hands)
+
+Options:
+    -h, --help              Print this help message
+        --config            Read the LED, format, user and parameters from a file,
+                            or from stdin if "-". Parameters override it.
+                            Defaults to /etc/morseclock.toml if it exists
+        --diagnose          Print the state of a LED and exit
+    -u, --user              User to drop privileges to after opening the LED, which
+                            only needs to be able to write the brightness and
+                            trigger files of the LED afterwards
+    -g, --group             Group to drop privileges to instead of the primary group
+                            of the user. Supplementary groups are always dropped
+        --chroot            Directory to change the root to before dropping to the
+                            user, requires --user
+        --wait-writable     Wait up to the given duration for the LED to become
+                            writable after dropping privileges
+    -f, --fifo              Write the symbols to a named pipe instead of a LED
+        --simulate          Draw the LED on the terminal instead of a LED
+        --split-leds        Display the hour on the LED DIR_A and the minute on
+                            the LED DIR_B at the same time
+        --rgb               Display the time on a RGB LED made up of three LEDs
+        --short-color       Color of the short blink on a RGB LED (default: 00ff00)
+        --long-color        Color of the long blink on a RGB LED (default: ff0000)
+        --ampm-led          LED_SYSFS_DIR of a second LED which is lit for PM times
+        --preamble          Blink a preamble (-.-.-) before every time
+        --eot               Blink an end of transmission (...-.-) after every time
+        --seconds           Also blink the seconds after the minute
+        --format            Display the time in the "12" (default) or "24" hour format
+        --timezone          Display the time of an IANA timezone, e.g. "Europe/Berlin",
+                            or of an offset, e.g. "+02:00", instead of the local time
+        --id                Blink this number once at startup to identify the unit
+        --on-level          Brightness of the LED while on (default: maximum)
+        --break-state       State of the LED during breaks: "off" (default),
+                            "on" to blink dark pulses or "dim"
+        --duty-brightness   Display the duty cycles as brightness instead of time,
+                            keeping the LED on for the whole symbol
+        --idle-pulse        Let the LED glow at this fraction of the maximum
+                            brightness between two times, e.g. 0.05
+    -w, --min-write-interval
+                            Minimum duration between two brightness writes
+        --proportional-restore
+                            Restore the previous brightness relative to the
+                            maximum brightness, which may have changed
+        --newline           Append a newline to every written brightness, for
+                            drivers which require it
+        --status            Print the number of displayed cycles on SIGUSR2
+        --status-file       Write the last displayed time and the number of displayed
+                            cycles as JSON to this file after every cycle
+        --once              Display the time a single time and exit
+        --repeat            Display every time this many times before the pause
+        --hold              Hold the LED in its break state after every time and
+                            display the next one on SIGUSR1
+        --print-durations   Print every displayed symbol with its on and off duration
+        --dry-run           Print the LED states of the current time with their
+                            durations instead of displaying them, without opening
+                            the LED
+    -v, --verbose           Log what is displayed and done to the LED, not only
+                            warnings, unless RUST_LOG selects the log levels instead
+        --pattern           Blink a pattern of '.', '-' and '=' over and over
+                            instead of the time
+        --self-test         Blink SOS (...---...) over and over instead of the time,
+                            to check the wiring and timing of the LED
+        --listen            Blink the messages received on a Unix socket at PATH
+                            instead of the time. Every line is either "short",
+                            "long", "break" or a time "HH:MM"
+        --on-no-time        What to do while the system time isn't set:
+                            "display" (default), "pattern" or "wait"
+
+"#
+    );
+}
 
-        trigger.unwrap_or(None)
+/// Parses the command line, reading the config at `default_config` if it exists and no
+/// `--config` is given
+fn args(mut args: pico_args::Arguments, default_config: &Path) -> anyhow::Result<Args> {
+    if args.contains(["-h", "--help"]) {
+        help();
+        process::exit(0);
     }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+    if let Some(path) =
+        args.opt_value_from_os_str::<_, _, Infallible>("--diagnose", |p| Ok(p.to_owned()))?
+    {
+        print!("{}", SysfsLed::diagnose(path));
+        process::exit(0);
+    }
 
-        #[test]
-        fn trigger() {
-            assert_eq!(parse_trigger("[none]"), None);
-            assert_eq!(parse_trigger("[usb-gadget]"), Some("usb-gadget"));
-            assert_eq!(parse_trigger("[cpu3]"), Some("cpu3"));
+    let config = args
+        .opt_value_from_os_str::<_, _, Infallible>("--config", |p| Ok(p.to_owned()))?
+        .or_else(|| default_config.exists().then(|| default_config.into()))
+        .map(Config::load)
+        .transpose()?;
+
+    // parameters passed on the command line override the ones of the config
+    let missing = |name: &str| anyhow::anyhow!("missing parameter {}", name);
+    let pause = args
+        .opt_value_from_str(["-p", "--pause-duration"])?
+        .or(config.as_ref().map(|c| c.pause))
+        .ok_or_else(|| missing("--pause-duration"))?;
+    let base_duration = args
+        .opt_value_from_str(["-b", "--base-duration"])?
+        .or(config
+            .as_ref()
+            .and_then(|c| c.base.as_millis().try_into().ok()))
+        .ok_or_else(|| missing("--base-duration"))?;
+    let timing = match args.opt_value_from_str::<_, Ratio>(["-r", "--ratio"])? {
+        Some(ratio) => Timing::from_ratio(base_duration, pause, ratio.short, ratio.long)?,
+        None => {
+            let long_duty = args
+                .opt_value_from_str::<_, DutyCycle>(["-l", "--long-duty"])?
+                .or(config.as_ref().map(|c| c.long_duty))
+                .ok_or_else(|| missing("--long-duty"))?;
+            let short_duty = args
+                .opt_value_from_str::<_, DutyCycle>(["-s", "--short-duty"])?
+                .or(config.as_ref().map(|c| c.short_duty))
+                .ok_or_else(|| missing("--short-duty"))?;
+
+            Timing::new(base_duration, pause, short_duty, long_duty)
         }
+    };
+    let timing = match args.opt_value_from_str("--speed")? {
+        Some(speed) => timing.scale(speed)?,
+        None => timing,
+    };
+    timing.validate(morseclock_bin::timing::MIN_DURATION)?;
+    let timing = match args.opt_value_from_str("--no-pauses")? {
+        Some(minimum) => timing.without_pauses(Duration::from_millis(minimum)),
+        None => timing,
+    };
+    let options = Options {
+        preamble: args.contains("--preamble"),
+        eot: args.contains("--eot"),
+        seconds: args.contains("--seconds"),
+        id: args.opt_value_from_str("--id")?,
+        max_cycle_duration: args
+            .opt_value_from_str("--max-cycle-duration")?
+            .map(Duration::from_millis),
+        status: args
+            .contains("--status")
+            .then(|| sync::Arc::new(Status::default())),
+        on_no_time: args.opt_value_from_str("--on-no-time")?.unwrap_or_default(),
+        hold: args
+            .contains("--hold")
+            .then(|| sync::Arc::new(Hold::default())),
+        once: args.contains("--once"),
+        status_file: args
+            .opt_value_from_os_str::<_, _, Infallible>("--status-file", |p| Ok(p.into()))?,
+        repeat: args.opt_value_from_str("--repeat")?.unwrap_or(1),
+        repeat_gap: args
+            .opt_value_from_str("--repeat-gap")?
+            .map(Duration::from_millis)
+            .unwrap_or(timing.durations(Symbol::Break(BreakKind::Group)).1 * 3),
+        #[cfg(feature = "systemd")]
+        notify: Some(sync::Arc::new(morseclock_bin::Systemd::from_env())),
+        ..Options::new(
+            args.opt_value_from_fn("--format", config::parse_format)?
+                .or(config.as_ref().map(|c| c.format))
+                .unwrap_or(Format::Hour12),
+        )
+    };
 
-        #[test]
-        fn find_trigger() {
-            assert_eq!(parse_trigger("some other"), None);
-            assert_eq!(parse_trigger("some other [none]"), None);
-            assert_eq!(parse_trigger("some [processor-14x] banana"), Some("processor-14x"));
-        }
+    let zone = args.opt_value_from_str("--timezone")?.unwrap_or_default();
+
+    let min_write_interval = args
+        .opt_value_from_str(["-w", "--min-write-interval"])?
+        .map(Duration::from_millis)
+        .unwrap_or_default();
+    let proportional_restore = args.contains("--proportional-restore");
+    let newline = args.contains("--newline");
+    let on_level = args.opt_value_from_str("--on-level")?;
+    let break_state = args
+        .opt_value_from_str("--break-state")?
+        .unwrap_or_default();
+    let duty_brightness = args.contains("--duty-brightness");
+    let idle_pulse = args.opt_value_from_str("--idle-pulse")?;
+    let short_color = args.opt_value_from_str("--short-color")?;
+    let long_color = args.opt_value_from_str("--long-color")?;
+    let ampm_led =
+        args.opt_value_from_os_str::<_, _, Infallible>("--ampm-led", |p| Ok(p.to_owned()))?;
+    let user = args
+        .opt_value_from_os_str::<_, _, Infallible>(["-u", "--user"], |u| Ok(u.to_owned()))?
+        .or(config
+            .as_ref()
+            .and_then(|c| c.user.clone().map(OsString::from)));
+    let group =
+        args.opt_value_from_os_str::<_, _, Infallible>(["-g", "--group"], |g| Ok(g.to_owned()))?;
+    let chroot =
+        args.opt_value_from_os_str::<_, _, Infallible>("--chroot", |p| Ok(p.to_owned()))?;
+    if chroot.is_some() && user.is_none() {
+        // root could leave the chroot again
+        anyhow::bail!("--chroot requires --user");
     }
-}
+    let print_durations = args.contains("--print-durations");
+    let dry_run = args.contains("--dry-run");
+    let verbose = args.contains(["-v", "--verbose"]);
+    let pattern = if args.contains("--self-test") {
+        Some(SymbolSequence(morseclock_bin::SELF_TEST.to_vec()))
+    } else {
+        args.opt_value_from_str("--pattern")?
+    };
+    let listen =
+        args.opt_value_from_os_str::<_, _, Infallible>("--listen", |p| Ok(p.to_owned()))?;
+    let wait_writable = args
+        .opt_value_from_str("--wait-writable")?
+        .map(Duration::from_millis);
+    let fifo =
+        args.opt_value_from_os_str::<_, _, Infallible>(["-f", "--fifo"], |f| Ok(f.to_owned()))?;
+    let simulate = args.contains("--simulate");
+    let split =
+        args.opt_value_from_os_str::<_, _, Infallible>("--split-leds", |p| Ok(p.to_owned()))?;
+    let rgb = args.opt_value_from_os_str("--rgb", |p| {
+        let dirs: Vec<_> = p.to_string_lossy().split(',').map(OsString::from).collect();
+        <[OsString; 3]>::try_from(dirs).map_err(|_| "expected RED_DIR,GREEN_DIR,BLUE_DIR")
+    })?;
+    let output = match (fifo, split, rgb) {
+        (Some(fifo), _, _) => Output::Fifo(fifo),
+        _ if simulate => Output::Simulate,
+        (None, Some(hour), _) => Output::Split(
+            hour,
+            args.opt_free_from_os_str::<_, Infallible>(|f| Ok(f.to_owned()))?
+                .ok_or_else(|| missing("DIR_B"))?,
+        ),
+        (None, None, Some(dirs)) => Output::Rgb(dirs),
+        (None, None, None) => {
+            match args.opt_free_from_os_str::<_, Infallible>(|f| Ok(f.to_owned()))? {
+                Some(path) => Output::Sysfs(path),
+                None => Output::Sysfs(
+                    config
+                        .map(|c| c.path.into_os_string())
+                        .ok_or_else(|| missing("LED_SYSFS_DIR"))?,
+                ),
+            }
+        }
+    };
 
-#[derive(Debug)]
-enum Error {
-    InvalidDutyCycle,
-    ParseError(num::ParseFloatError),
+    Ok(Args {
+        timing,
+        options,
+        zone,
+        min_write_interval,
+        proportional_restore,
+        newline,
+        on_level,
+        break_state,
+        duty_brightness,
+        idle_pulse,
+        short_color,
+        long_color,
+        ampm_led,
+        user,
+        group,
+        chroot,
+        wait_writable,
+        print_durations,
+        dry_run,
+        verbose,
+        pattern,
+        listen,
+        output,
+    })
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::InvalidDutyCycle => f.write_str("Invalid duty cycle"),
-            Self::ParseError(e) => write!(f, "Parsing failed: {}", e),
+fn app() -> anyhow::Result<()> {
+    let args = match args(
+        pico_args::Arguments::from_env(),
+        Path::new(config::DEFAULT_PATH),
+    ) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Argument error: {}", e);
+            help();
+            process::exit(1);
         }
-    }
-}
+    };
 
-impl error::Error for Error {}
+    let default_level = if args.verbose { "debug" } else { "warn" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level))
+        .init();
+    log::info!("Displaying on {:?} with {:?}", args.output, args.timing);
 
-impl From<num::ParseFloatError> for Error {
-    fn from(error: num::ParseFloatError) -> Self {
-        Self::ParseError(error)
+    for symbol in args.timing.invisible_blinks() {
+        log::warn!(
+            "The {:?} blink is on for less than 1ms and will be invisible",
+            symbol
+        );
     }
-}
-
-#[derive(Debug)]
-struct SysfsLed {
-    max_brightness: u32,
-    old_brightness: u32,
-    trigger: Option<String>,
-    brightness_file: fs::File,
-    trigger_file: fs::File,
-}
-
-impl SysfsLed {
-    pub fn new<P: AsRef<path::Path>>(path: P) -> anyhow::Result<Self> {
-        let path = path.as_ref();
 
-        // Generate all the necessary paths
-        let brightness_path: path::PathBuf =
-            [path, path::Path::new("brightness")].into_iter().collect();
-        let trigger_path: path::PathBuf = [path, path::Path::new("trigger")].into_iter().collect();
-        let max_brightness_path: path::PathBuf = [path, path::Path::new("max_brightness")]
-            .into_iter()
-            .collect();
+    if args.dry_run {
+        return morseclock_bin::write_schedule(
+            io::stdout().lock(),
+            &args.timing,
+            &args.options,
+            &args.zone,
+        );
+    }
 
-        let trigger = fs::read_to_string(&trigger_path)?;
-        let max_brightness = fs::read_to_string(&max_brightness_path)?;
-        let old_brightness = fs::read_to_string(&brightness_path)?;
+    let mut led: Box<dyn Led> = match &args.output {
+        Output::Sysfs(path) | Output::Split(path, _) => Box::new(open_sysfs(&args, path)?),
+        Output::Fifo(path) => Box::new(FifoLed::open(path)?),
+        Output::Simulate => Box::new(SimLed::stdout()),
+        Output::Rgb(dirs) => {
+            let mut led = RgbSysfsLed::new(dirs.clone())?;
 
-        let mut trigger_file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(trigger_path)?;
+            if let Some(color) = args.short_color {
+                led.set_color(Symbol::Short, color);
+            }
+            if let Some(color) = args.long_color {
+                led.set_color(Symbol::Long, color);
+            }
 
-        Self::write_trigger(&mut trigger_file, "none")?;
+            Box::new(led)
+        }
+    };
+    let mut minute_led = match &args.output {
+        Output::Split(_, path) => Some(open_sysfs(&args, path)?),
+        _ => None,
+    };
 
-        Ok(SysfsLed {
-            max_brightness: max_brightness.trim().parse()?,
-            old_brightness: old_brightness.trim().parse()?,
-            trigger: parser::parse_trigger(&trigger).map(|t| t.to_owned()),
-            brightness_file: fs::OpenOptions::new()
-                .read(true)
-                .write(true)
-                .open(brightness_path)?,
-            trigger_file,
-        })
+    if let Some(path) = &args.ampm_led {
+        led = Box::new(AmPmLed::new(led, SysfsLed::new(path)?));
     }
 
-    pub fn set(&mut self, value: u32) -> anyhow::Result<()> {
-        self.brightness_file.seek(io::SeekFrom::Start(0))?;
-        self.brightness_file.write_fmt(format_args!("{}", value))?;
+    if args.print_durations {
+        led = Box::new(Inspect::new(led, |symbol, on, off| {
+            eprintln!("{:?}: on {:?}, off {:?}", symbol, on, off)
+        }));
+    }
 
-        Ok(())
+    // drop to an unprivileged user, after the LED files have been opened
+    if let Some(privdrop) = priv_drop(&args) {
+        privdrop.apply()?;
+        log::info!(
+            "Dropped privileges to user {:?}, group {:?}, chroot {:?}",
+            args.user,
+            args.group,
+            args.chroot
+        );
     }
 
-    fn write_trigger(file: &mut fs::File, trigger: &str) -> anyhow::Result<()> {
-        file.seek(io::SeekFrom::Start(0))?;
-        file.write_all(trigger.as_bytes())?;
+    if let Some(timeout) = args.wait_writable {
+        morseclock_bin::wait_writable(|| led.off(), timeout)?;
 
-        Ok(())
+        if let Some(minute_led) = &mut minute_led {
+            morseclock_bin::wait_writable(|| minute_led.off(), timeout)?;
+        }
     }
 
-    fn reset_trigger(&mut self) -> anyhow::Result<()> {
-        if let Some(trigger) = &self.trigger {
-            Self::write_trigger(&mut self.trigger_file, trigger)
-        } else {
-            Ok(())
-        }
+    if let Some(notify) = &args.options.notify {
+        notify.ready()?;
     }
-}
 
-impl Drop for SysfsLed {
-    fn drop(&mut self) {
-        self.set(self.old_brightness).unwrap();
-        self.reset_trigger().unwrap();
+    let running = sync::Arc::new(atomic::AtomicBool::new(true));
+    stop_on_termination(&running)?;
+
+    if let Some(status) = args.options.status.clone() {
+        let mut signals = Signals::new([SIGUSR2])?;
+
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                eprintln!("Displayed cycles: {}", status.cycles());
+            }
+        });
     }
-}
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
-struct DutyCycle(f64);
+    if let Some(hold) = args.options.hold.clone() {
+        let mut signals = Signals::new([SIGUSR1])?;
+
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                hold.advance();
+            }
+        });
+    }
 
-impl str::FromStr for DutyCycle {
-    type Err = Error;
+    if let Some(minute_led) = &mut minute_led {
+        return morseclock_bin::run_split(
+            &mut led,
+            minute_led,
+            &args.timing,
+            &args.options,
+            &args.zone,
+            &running,
+        );
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value = s.parse()?;
+    if let Some(path) = &args.listen {
+        return listen(&mut led, &args.timing, &args.options, path, &running);
+    }
 
-        if value <= 0.0 || value > 1.0 {
-            Err(Error::InvalidDutyCycle)
-        } else {
-            Ok(DutyCycle(value))
+    match &args.pattern {
+        Some(pattern) => morseclock_bin::run_pattern(
+            &mut led,
+            &args.timing,
+            &pattern.0,
+            &running,
+            args.options.notify.as_deref(),
+        ),
+        None => {
+            morseclock_bin::run_with(&mut led, &args.timing, &args.options, &args.zone, &running)
         }
     }
 }
 
-#[derive(Debug)]
-pub struct Args {
-    pub base_duration: u64,
-    pub break_duration: u64,
-    pub short_on_duration: u64,
-    pub short_off_duration: u64,
-    pub long_on_duration: u64,
-    pub long_off_duration: u64,
-    pub user: Option<OsString>,
-    pub path: OsString,
-}
+/// Clears `running` on SIGINT, SIGTERM or SIGHUP
+///
+/// The display loop returns shortly afterwards, so the LEDs are restored when they
+/// are dropped, e.g. also when systemd stops the service. SIGTERM and SIGHUP are
+/// handled by the `termination` feature of `ctrlc`.
+fn stop_on_termination(running: &sync::Arc<atomic::AtomicBool>) -> anyhow::Result<()> {
+    let running = running.clone();
+
+    ctrlc::set_handler(move || {
+        eprintln!("Exiting..");
+        log::info!("Received a termination signal, stopping the display loop");
+        running.store(false, atomic::Ordering::Relaxed);
+    })?;
 
-fn help() {
-    println!(
-        r#"
-morseclock-hw - Yet another not-so-useful LED clock
+    Ok(())
+}
 
-Usage: morseclock-hw [PARAMS] [OPTIONS] LED_SYSFS_DIR
+/// The privileges to drop to selected by `args`, if any
+///
+/// Unless `--group` is given, the primary group of the user is switched to. The
+/// supplementary groups are set to just that group.
+fn priv_drop(args: &Args) -> Option<privdrop::PrivDrop> {
+    if args.user.is_none() && args.group.is_none() {
+        return None;
+    }
 
-Parameters:
-    -p, --pause-duration    Duration of pause between hour and minute
-    -b, --base-duration     Base duration of a blink
-    -l, --long-duty         Duty cycle of the long blink
-    -s, --short-duration    Duty cycle of the short blink
+    let mut privdrop = privdrop::PrivDrop::default();
 
-Options:
-    -h, --help              Print this help message
-    -u, --user              User to drop privileges to
+    if let Some(chroot) = &args.chroot {
+        privdrop = privdrop.chroot(chroot);
+    }
+    if let Some(user) = &args.user {
+        privdrop = privdrop.user(user);
+    }
+    if let Some(group) = &args.group {
+        privdrop = privdrop.group(group);
+    }
 
-"#
-    );
+    Some(privdrop)
 }
 
-fn args() -> anyhow::Result<Args> {
-    let mut args = pico_args::Arguments::from_env();
+/// Opens the sysfs LED at `path` with the LED options of `args`
+fn open_sysfs(args: &Args, path: &OsString) -> anyhow::Result<SysfsLed> {
+    let mut led = SysfsLed::builder(path)
+        .min_write_interval(args.min_write_interval)
+        .proportional_restore(args.proportional_restore)
+        .newline(args.newline)
+        .build()?;
 
-    if args.contains(["-h", "--help"]) {
-        help();
-        process::exit(0);
+    if let Some(level) = args.on_level {
+        led.set_on_level(level);
     }
+    led.set_break_state(args.break_state);
+    led.set_duty_brightness(args.duty_brightness);
+    led.set_idle_fraction(args.idle_pulse.map(|fraction| fraction.value()));
 
-    let break_duration = args.value_from_str(["-p", "--pause-duration"])?;
-    let base_duration = args.value_from_str(["-b", "--base-duration"])?;
-    let long_duty = args.value_from_str::<_, DutyCycle>(["-l", "--long-duty"])?;
-    let short_duty = args.value_from_str::<_, DutyCycle>(["-s", "--short-duty"])?;
-
-    Ok(Args {
-        base_duration,
-        break_duration,
-        short_on_duration: (base_duration as f64 * short_duty.0) as u64,
-        short_off_duration: (base_duration as f64 * (1.0 - short_duty.0)) as u64,
-        long_on_duration: (base_duration as f64 * long_duty.0) as u64,
-        long_off_duration: (base_duration as f64 * (1.0 - long_duty.0)) as u64,
-        user: args
-            .opt_value_from_os_str::<_, _, Infallible>(["-u", "--user"], |u| Ok(u.to_owned()))?,
-        path: args.free_from_os_str::<_, Infallible>(|f| Ok(f.to_owned()))?,
-    })
+    Ok(led)
 }
 
-fn blink(led: &mut SysfsLed, on_duration: Duration, off_duration: Duration) -> anyhow::Result<()> {
-    led.set(led.max_brightness)?;
-    thread::sleep(on_duration);
-    led.set(0)?;
-    thread::sleep(off_duration);
+/// Displays the messages of one client after the other on the Unix socket at `path`
+///
+/// The watchdog of [`Options::notify`] is pinged while waiting for clients and messages.
+fn listen<L: Led>(
+    led: &mut L,
+    timing: &Timing,
+    options: &Options,
+    path: &OsString,
+    running: &atomic::AtomicBool,
+) -> anyhow::Result<()> {
+    const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+    let notify = options.notify.as_deref();
+    let mut watchdog = Watchdog::new(notify);
+    let poll_interval = POLL_INTERVAL.min(watchdog.poll_interval().unwrap_or(POLL_INTERVAL));
+    let listener = UnixListener::bind(path)?;
+    listener.set_nonblocking(true)?;
+
+    while running.load(atomic::Ordering::Relaxed) {
+        watchdog.poll()?;
+
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                stream.set_read_timeout(Some(poll_interval))?;
+
+                let reader = io::BufReader::new(stream);
+                if let Err(e) =
+                    stream::run_stream(led, timing, options.format, reader, running, notify)
+                {
+                    eprintln!("Client error: {}", e);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(poll_interval),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    std::fs::remove_file(path)?;
 
     Ok(())
 }
 
-fn app() -> anyhow::Result<()> {
-    let args = match args() {
-        Ok(args) => args,
-        Err(e) => {
-            eprintln!("Argument error: {}", e);
-            help();
-            process::exit(1);
-        }
-    };
+fn main() {
+    if let Err(e) = app() {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
 
-    let mut led = SysfsLed::new(&args.path)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morseclock::MorseExt;
+    use morseclock_bin::Pause;
+    use std::fs;
 
-    // drop to an unprivileged user
-    if let Some(user) = args.user {
-        privdrop::PrivDrop::default().user(user).apply()?;
+    /// Parses `argv` without reading the config of the host
+    fn parse(argv: &[&str]) -> anyhow::Result<Args> {
+        parse_with_default(argv, Path::new("/nonexistent/morseclock.toml"))
     }
 
-    // break up the break duration into smaller chunks of ~ 200 ms to be able to exit ASAP
-    let (break_duration, break_repeats) = if args.break_duration <= 200 {
-        (args.break_duration, 1)
-    } else {
-        let approx_repeats = args.break_duration / 200;
-        let approx_break_duration = args.break_duration / approx_repeats;
+    fn parse_with_default(argv: &[&str], default_config: &Path) -> anyhow::Result<Args> {
+        args(
+            pico_args::Arguments::from_vec(argv.iter().map(OsString::from).collect()),
+            default_config,
+        )
+    }
 
-        (
-            approx_break_duration,
-            args.break_duration / approx_break_duration,
+    #[test]
+    fn toml_config() {
+        let file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        fs::write(
+            file.path(),
+            r#"
+            path = "/sys/class/leds/foo"
+            format = 24
+            base_duration = 200
+            break_duration = "auto"
+            short_duty = 0.25
+            long_duty = 0.75
+            user = "nobody"
+            "#,
         )
-    };
+        .unwrap();
+        let config = file.path().to_str().unwrap();
+
+        let args = parse(&["--config", config]).unwrap();
+        assert_eq!(
+            args.timing,
+            Timing::new(200, Pause::NextMinute, DutyCycle(0.25), DutyCycle(0.75))
+        );
+        assert_eq!(args.options.format, Format::Hour24);
+        assert_eq!(args.user, Some("nobody".into()));
+        assert!(matches!(args.output, Output::Sysfs(p) if p == "/sys/class/leds/foo"));
+
+        // parameters passed on the command line take precedence
+        let args = parse(&[
+            "--config", config, "-b", "100", "-l", "0.5", "--format", "12", "-u", "root", "led",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.timing,
+            Timing::new(100, Pause::NextMinute, DutyCycle(0.25), DutyCycle(0.5))
+        );
+        assert_eq!(args.options.format, Format::Hour12);
+        assert_eq!(args.user, Some("root".into()));
+        assert!(matches!(args.output, Output::Sysfs(p) if p == "led"));
+    }
 
-    let running = sync::Arc::new(atomic::AtomicBool::new(true));
+    #[test]
+    fn termination_signals() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("max_brightness"), "255\n").unwrap();
+
+        let running = sync::Arc::new(atomic::AtomicBool::new(true));
+        stop_on_termination(&running).unwrap();
+        let timing = Timing::new(
+            10,
+            Pause::Fixed(Duration::from_secs(3600)),
+            DutyCycle(0.2),
+            DutyCycle(0.6),
+        );
+        let options = Options::new(Format::Hour12);
+
+        for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGHUP] {
+            fs::write(dir.path().join("brightness"), "42\n").unwrap();
+            fs::write(dir.path().join("trigger"), "none [timer]\n").unwrap();
+            running.store(true, atomic::Ordering::Relaxed);
+            let mut led = SysfsLed::new(dir.path()).unwrap();
+
+            thread::scope(|s| {
+                let display = s.spawn(|| {
+                    morseclock_bin::run_with(&mut led, &timing, &options, &Zone::Local, &running)
+                });
+
+                // the signal arrives during the long pause
+                thread::sleep(Duration::from_millis(200));
+                signal_hook::low_level::raise(signal).unwrap();
+                display.join().unwrap().unwrap();
+            });
 
-    ctrlc::set_handler({
-        let running = running.clone();
-        move || {
-            eprintln!("Exiting..");
-            running.store(false, atomic::Ordering::Relaxed);
+            assert_eq!(
+                fs::read_to_string(dir.path().join("trigger")).unwrap(),
+                "none"
+            );
+            drop(led);
+            assert_eq!(
+                fs::read_to_string(dir.path().join("brightness")).unwrap(),
+                "42"
+            );
+            assert_eq!(
+                fs::read_to_string(dir.path().join("trigger")).unwrap(),
+                "timer"
+            );
         }
-    })?;
+    }
 
-    'outer: while running.load(atomic::Ordering::Relaxed) {
-        let now = Local::now();
-        let hour = now.hour().try_into()?;
-        let minute = now.minute().try_into()?;
+    #[test]
+    fn privileges() {
+        let parse = |privileges: &[&str]| {
+            let timing = ["-p", "1000", "-b", "100", "-l", "0.5", "-s", "0.2", "led"];
+            parse(&[privileges, &timing].concat())
+        };
+
+        let args = parse(&[]).unwrap();
+        assert!(args.user.is_none() && args.group.is_none() && args.chroot.is_none());
+        assert!(priv_drop(&args).is_none());
+
+        let args = parse(&["-u", "nobody"]).unwrap();
+        assert_eq!(args.user, Some("nobody".into()));
+        assert!(args.group.is_none());
+        assert!(priv_drop(&args).is_some());
+
+        let args = parse(&["--user", "nobody", "--group", "leds"]).unwrap();
+        assert_eq!(args.group, Some("leds".into()));
+
+        let args = parse(&["-g", "leds"]).unwrap();
+        assert!(args.user.is_none());
+        assert!(priv_drop(&args).is_some());
+
+        let args = parse(&["-u", "nobody", "-g", "leds", "--chroot", "/var/empty"]).unwrap();
+        assert_eq!(args.chroot, Some("/var/empty".into()));
+        assert!(priv_drop(&args).is_some());
+
+        for privileges in [
+            &["--chroot", "/var/empty"][..],
+            &["-g", "leds", "--chroot", "/"],
+        ] {
+            let error = parse(privileges).unwrap_err();
+            assert_eq!(error.to_string(), "--chroot requires --user");
+        }
+    }
 
-        let clock = Clock::new(hour, minute, Format::Hour12);
+    #[test]
+    fn too_short() {
+        let error = parse(&["-p", "1000", "-b", "0", "-l", "0.5", "-s", "0.2", "led"]).unwrap_err();
+        assert_eq!(error.to_string(), "The base duration must be at least 1ms");
 
-        for sym in clock {
-            if !running.load(atomic::Ordering::Relaxed) {
-                break 'outer;
-            }
+        let error = parse(&["-p", "0", "-b", "100", "-l", "0.5", "-s", "0.2", "led"]).unwrap_err();
+        assert_eq!(error.to_string(), "The pause duration must be at least 1ms");
 
-            match sym {
-                Symbol::Break => {
-                    thread::sleep(Duration::from_millis(args.base_duration));
-                }
-                Symbol::Short => {
-                    blink(
-                        &mut led,
-                        Duration::from_millis(args.short_on_duration),
-                        Duration::from_millis(args.short_off_duration),
-                    )?;
-                }
-                Symbol::Long => {
-                    blink(
-                        &mut led,
-                        Duration::from_millis(args.long_on_duration),
-                        Duration::from_millis(args.long_off_duration),
-                    )?;
-                }
-            }
-        }
+        // an invisible blink is only warned about
+        let args = parse(&["-p", "1000", "-b", "5", "-l", "0.5", "-s", "0.1", "led"]).unwrap();
+        assert_eq!(args.timing.invisible_blinks(), [Symbol::Short]);
+    }
 
-        for _ in 0..break_repeats {
-            if !running.load(atomic::Ordering::Relaxed) {
-                break 'outer;
-            }
+    #[test]
+    fn self_test() {
+        let args = parse(&[
+            "-p",
+            "1000",
+            "-b",
+            "100",
+            "-l",
+            "0.5",
+            "-s",
+            "0.2",
+            "--self-test",
+            "led",
+        ])
+        .unwrap();
+        let pattern = args.pattern.unwrap();
+
+        assert_eq!(pattern.0, morseclock_bin::SELF_TEST);
+        assert_eq!(
+            pattern.0.into_iter().morse().collect::<String>(),
+            "...---..."
+        );
+    }
 
-            thread::sleep(Duration::from_millis(break_duration));
-        }
+    #[test]
+    fn repeat() {
+        let args = parse(&["-p", "1000", "-b", "100", "-l", "0.5", "-s", "0.2", "led"]).unwrap();
+        assert_eq!(args.options.repeat, 1);
+
+        let argv = [
+            "-p", "1000", "-b", "100", "-l", "0.5", "-s", "0.2", "--repeat", "3", "led",
+        ];
+        let args = parse(&argv).unwrap();
+        assert_eq!(args.options.repeat, 3);
+        assert_eq!(args.options.repeat_gap, Duration::from_millis(300));
+
+        let args = parse(&[&argv[..10], &["--repeat-gap", "50", "led"]].concat()).unwrap();
+        assert_eq!(args.options.repeat_gap, Duration::from_millis(50));
     }
 
-    Ok(())
-}
+    #[test]
+    fn invalid_toml_config() {
+        let file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        fs::write(
+            file.path(),
+            "path = 'led'\nbase_duration = 200\nbreak_duration = 1000\n\
+             short_duty = 0.25\nlong_duty = 1.25\n",
+        )
+        .unwrap();
 
-fn main() {
-    if let Err(e) = app() {
-        eprintln!("Error: {}", e);
-        process::exit(1);
+        let error = parse(&["--config", file.path().to_str().unwrap()]).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid duty cycle");
+    }
+
+    #[test]
+    fn default_config() {
+        let file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        fs::write(
+            file.path(),
+            "path = 'led'\nformat = 24\nbase_duration = 200\nbreak_duration = 1000\n\
+             short_duty = 0.25\nlong_duty = 0.75\n",
+        )
+        .unwrap();
+
+        let args = parse_with_default(&[], file.path()).unwrap();
+        assert_eq!(args.options.format, Format::Hour24);
+        assert!(matches!(args.output, Output::Sysfs(p) if p == "led"));
+
+        // an explicit config replaces the default one
+        let error = parse_with_default(&["--config", "/nonexistent.toml"], file.path());
+        assert!(error.is_err());
     }
 }
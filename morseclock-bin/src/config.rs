@@ -0,0 +1,356 @@
+//! A configuration of the clock, stored in a string, a file or passed via stdin
+
+use crate::timing::{self, DutyCycle, Pause, Timing};
+use crate::Error;
+use morseclock::Format;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str;
+use std::time::Duration;
+
+/// The configuration file read if none is given
+pub const DEFAULT_PATH: &str = "/etc/morseclock.toml";
+
+/// The LED, format and timing of a clock
+///
+/// Parsed from a list of `key=value` pairs separated by commas or newlines, e.g.
+/// `path=/sys/class/leds/foo,format=24,base=200ms,pause=1s,short=0.3,long=0.7`.
+///
+/// | Key      | Value                                                 |
+/// |----------|-------------------------------------------------------|
+/// | `path`   | The sysfs directory of the LED                        |
+/// | `format` | `12` or `24`, optional and defaults to `12`           |
+/// | `base`   | The base duration of a blink                          |
+/// | `pause`  | The pause between two displayed times, see [`Pause`]  |
+/// | `short`  | The duty cycle of the short blink                     |
+/// | `long`   | The duty cycle of the long blink                      |
+/// | `user`   | The user to drop privileges to, optional              |
+///
+/// Durations are given in milliseconds, optionally with a unit of `ms` or `s`.
+///
+/// Files ending in `.toml` are parsed by [`Config::from_toml`] instead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub path: PathBuf,
+    pub user: Option<String>,
+    pub format: Format,
+    pub base: Duration,
+    pub pause: Pause,
+    pub short_duty: DutyCycle,
+    pub long_duty: DutyCycle,
+}
+
+impl Config {
+    /// Reads and parses a configuration from `reader`
+    pub fn from_reader<R: Read>(mut reader: R) -> anyhow::Result<Self> {
+        let mut config = String::new();
+        reader.read_to_string(&mut config)?;
+
+        Ok(config.parse()?)
+    }
+
+    /// Parses a configuration from a TOML document
+    ///
+    /// The keys are `path`, `format`, `base_duration`, `break_duration`, `short_duty`,
+    /// `long_duty` and `user`, with the same values as `path`, `format`, `base`, `pause`,
+    /// `short`, `long` and `user` of the `key=value` pairs, e.g.
+    ///
+    /// ```toml
+    /// path = "/sys/class/leds/foo"
+    /// format = 24
+    /// base_duration = "200ms"
+    /// break_duration = "auto"
+    /// short_duty = 0.3
+    /// long_duty = 0.7
+    /// ```
+    pub fn from_toml(s: &str) -> Result<Self, Error> {
+        let table: toml::Table = s
+            .parse()
+            .map_err(|e: toml::de::Error| Error::InvalidConfig(e.message().to_owned()))?;
+        let mut pairs = Vec::new();
+
+        for (key, value) in &table {
+            let invalid = || Error::InvalidConfig(format!("{} = {}", key, value));
+            let name = match key.as_str() {
+                "path" | "format" | "user" => key.as_str(),
+                "base_duration" => "base",
+                "break_duration" => "pause",
+                "short_duty" => "short",
+                "long_duty" => "long",
+                _ => return Err(invalid()),
+            };
+            let value = match value {
+                toml::Value::String(s) => s.clone(),
+                toml::Value::Integer(i) => i.to_string(),
+                toml::Value::Float(f) => f.to_string(),
+                _ => return Err(invalid()),
+            };
+
+            pairs.push((name, value));
+        }
+
+        Self::from_pairs(pairs.iter().map(|(key, value)| (*key, value.as_str())))
+    }
+
+    /// Loads the configuration in the file at `path`, or from stdin if `path` is `-`
+    pub fn load<P: AsRef<OsStr>>(path: P) -> anyhow::Result<Self> {
+        let path = Path::new(path.as_ref());
+
+        if path == Path::new("-") {
+            Self::from_reader(io::stdin().lock())
+        } else if path.extension().is_some_and(|ext| ext == "toml") {
+            Ok(Self::from_toml(&fs::read_to_string(path)?)?)
+        } else {
+            Self::from_reader(fs::File::open(path)?)
+        }
+    }
+
+    /// The timing described by the configuration
+    pub fn timing(&self) -> Result<Timing, Error> {
+        let base = self
+            .base
+            .as_millis()
+            .try_into()
+            .map_err(|_| Error::InvalidDuration)?;
+
+        Ok(Timing::new(
+            base,
+            self.pause,
+            self.short_duty,
+            self.long_duty,
+        ))
+    }
+}
+
+impl str::FromStr for Config {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pairs = s
+            .split([',', '\n'])
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                pair.split_once('=')
+                    .ok_or_else(|| Error::InvalidConfig(pair.to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::from_pairs(pairs)
+    }
+}
+
+impl Config {
+    /// Parses a configuration from its keys and values
+    fn from_pairs<'a, I>(pairs: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut path = None;
+        let mut user = None;
+        let mut format = Format::Hour12;
+        let mut base = None;
+        let mut pause = None;
+        let mut short = None;
+        let mut long = None;
+
+        for (key, value) in pairs {
+            match key.trim() {
+                "path" => path = Some(PathBuf::from(value)),
+                "user" => user = Some(value.to_owned()),
+                "format" => format = parse_format(value)?,
+                "base" => base = Some(timing::parse_duration(value)?),
+                "pause" => pause = Some(value.parse::<Pause>()?),
+                "short" => short = Some(value.parse::<DutyCycle>()?),
+                "long" => long = Some(value.parse::<DutyCycle>()?),
+                _ => return Err(Error::InvalidConfig(format!("{}={}", key, value))),
+            }
+        }
+
+        Ok(Config {
+            path: path.ok_or(Error::MissingKey("path"))?,
+            user,
+            format,
+            base: base.ok_or(Error::MissingKey("base"))?,
+            pause: pause.ok_or(Error::MissingKey("pause"))?,
+            short_duty: short.ok_or(Error::MissingKey("short"))?,
+            long_duty: long.ok_or(Error::MissingKey("long"))?,
+        })
+    }
+}
+
+/// Parses `12` or `24` into the corresponding [`Format`]
+pub fn parse_format(s: &str) -> Result<Format, Error> {
+    s.parse().map_err(|_| Error::InvalidFormat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete() {
+        let config: Config =
+            "path=/sys/class/leds/foo,format=24,base=200ms,pause=1s,short=0.3,long=0.7"
+                .parse()
+                .unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                path: PathBuf::from("/sys/class/leds/foo"),
+                user: None,
+                format: Format::Hour24,
+                base: Duration::from_millis(200),
+                pause: Pause::Fixed(Duration::from_secs(1)),
+                short_duty: DutyCycle(0.3),
+                long_duty: DutyCycle(0.7),
+            }
+        );
+        assert_eq!(
+            config.timing().unwrap(),
+            Timing::new(
+                200,
+                Pause::Fixed(Duration::from_secs(1)),
+                DutyCycle(0.3),
+                DutyCycle(0.7)
+            )
+        );
+
+        let config: Config = "long=0.5,short=0.1,pause=auto,base=500,path=led"
+            .parse()
+            .unwrap();
+        assert_eq!(config.format, Format::Hour12);
+        assert_eq!(config.pause, Pause::NextMinute);
+        assert_eq!(config.base, Duration::from_millis(500));
+
+        let config: Config = "long=0.5,short=0.1,pause=align:30s,base=500,path=led"
+            .parse()
+            .unwrap();
+        assert_eq!(config.pause, Pause::Aligned(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn reader() {
+        let input =
+            "path=/sys/class/leds/foo\nformat=24\n\nbase=1s, pause=auto\nshort=0.25\nlong=0.75\n";
+        let config = Config::from_reader(input.as_bytes()).unwrap();
+
+        assert_eq!(config.path, PathBuf::from("/sys/class/leds/foo"));
+        assert_eq!(config.format, Format::Hour24);
+        assert_eq!(config.base, Duration::from_secs(1));
+        assert_eq!(config.pause, Pause::NextMinute);
+        assert_eq!(config.short_duty, DutyCycle(0.25));
+        assert_eq!(config.long_duty, DutyCycle(0.75));
+
+        assert!(Config::from_reader("path=led\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn toml() {
+        let config = Config::from_toml(
+            r#"
+            path = "/sys/class/leds/foo"
+            format = 24
+            base_duration = "200ms"
+            break_duration = 1000
+            short_duty = 0.3
+            long_duty = 0.7
+            user = "nobody"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                path: PathBuf::from("/sys/class/leds/foo"),
+                user: Some("nobody".to_owned()),
+                format: Format::Hour24,
+                base: Duration::from_millis(200),
+                pause: Pause::Fixed(Duration::from_secs(1)),
+                short_duty: DutyCycle(0.3),
+                long_duty: DutyCycle(0.7),
+            }
+        );
+
+        let file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        fs::write(
+            file.path(),
+            "path = 'led'\nbase_duration = 500\nbreak_duration = 'auto'\n\
+             short_duty = 0.25\nlong_duty = 1\n",
+        )
+        .unwrap();
+        let config = Config::load(file.path()).unwrap();
+        assert_eq!(config.format, Format::Hour12);
+        assert_eq!(config.pause, Pause::NextMinute);
+        assert_eq!(config.long_duty, DutyCycle(1.0));
+    }
+
+    #[test]
+    fn invalid_toml() {
+        let complete = "path = 'led'\nbase_duration = 200\nbreak_duration = 1000\n";
+
+        for duties in [
+            "short_duty = 0.3\nlong_duty = 1.5",
+            "short_duty = 0\nlong_duty = 0.7",
+            "short_duty = '0.3'\nlong_duty = true",
+            "short_duty = 0.3\nlong_duty = 0.7\ncolor = 'red'",
+            "short_duty = 0.3\nlong = 0.7",
+            "short_duty = 0.3\nlong_duty = 0.7\nformat = 13",
+            "short_duty = 0.3\nlong_duty = [0.7]",
+            "short_duty = 0.3\nlong_duty =",
+        ] {
+            let toml = format!("{}{}", complete, duties);
+            assert!(Config::from_toml(&toml).is_err(), "{}", toml);
+        }
+
+        assert!(matches!(
+            Config::from_toml("short_duty = 0.3\nlong_duty = 1.5"),
+            Err(Error::InvalidDutyCycle)
+        ));
+        assert!(matches!(
+            Config::from_toml(complete),
+            Err(Error::MissingKey("short"))
+        ));
+    }
+
+    #[test]
+    fn missing_key() {
+        let complete = [
+            "path=led",
+            "base=200ms",
+            "pause=1s",
+            "short=0.3",
+            "long=0.7",
+        ];
+
+        for (i, key) in ["path", "base", "pause", "short", "long"]
+            .iter()
+            .enumerate()
+        {
+            let mut pairs = complete.to_vec();
+            pairs.remove(i);
+
+            assert!(matches!(
+                pairs.join(",").parse::<Config>(),
+                Err(Error::MissingKey(missing)) if missing == *key
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        for spec in [
+            "path=led,base=200ms,pause=1s,short=0.3,long=0.7,color=red",
+            "path=led,base=200ms,pause=1s,short=0.3,long",
+            "path=led,base=200ms,pause=1s,short=0.3,long=1.5",
+            "path=led,base=2h,pause=1s,short=0.3,long=0.7",
+            "path=led,format=13,base=200ms,pause=1s,short=0.3,long=0.7",
+        ] {
+            assert!(spec.parse::<Config>().is_err(), "{}", spec);
+        }
+    }
+}
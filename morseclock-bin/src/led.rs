@@ -0,0 +1,1825 @@
+//! LED backends which can be driven by [`run`](crate::run)
+
+use crate::{parser, Error};
+use anyhow::Context;
+use chrono::{NaiveDateTime, Timelike};
+use morseclock::{Meridiem, Symbol};
+use std::fs;
+use std::io::Write;
+use std::path;
+use std::str;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A LED which can be switched on and off
+pub trait Led {
+    /// Switches the LED on
+    fn on(&mut self) -> anyhow::Result<()>;
+    /// Switches the LED off
+    fn off(&mut self) -> anyhow::Result<()>;
+
+    /// Displays a single symbol
+    ///
+    /// The LED is kept on for `on` and off for `off`. A [`Symbol::Break`] or
+    /// [`Symbol::Meridiem`] never switches the LED on but only waits for `off`.
+    fn display(&mut self, symbol: Symbol, on: Duration, off: Duration) -> anyhow::Result<()> {
+        blink(self, symbol, on, off)
+    }
+
+    /// Called before the time `now` is displayed
+    fn start_cycle(&mut self, _now: NaiveDateTime) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called after a time has been displayed
+    fn end_cycle(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Displays `symbol` by switching `led` on for `on` and off for `off`, see [`Led::display`]
+fn blink<L: Led + ?Sized>(
+    led: &mut L,
+    symbol: Symbol,
+    on: Duration,
+    off: Duration,
+) -> anyhow::Result<()> {
+    if matches!(symbol, Symbol::Short | Symbol::Long) {
+        led.on()?;
+        thread::sleep(on);
+        led.off()?;
+    }
+
+    thread::sleep(off);
+
+    Ok(())
+}
+
+impl<L: Led + ?Sized> Led for Box<L> {
+    fn on(&mut self) -> anyhow::Result<()> {
+        (**self).on()
+    }
+
+    fn off(&mut self) -> anyhow::Result<()> {
+        (**self).off()
+    }
+
+    fn display(&mut self, symbol: Symbol, on: Duration, off: Duration) -> anyhow::Result<()> {
+        (**self).display(symbol, on, off)
+    }
+
+    fn start_cycle(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
+        (**self).start_cycle(now)
+    }
+
+    fn end_cycle(&mut self) -> anyhow::Result<()> {
+        (**self).end_cycle()
+    }
+}
+
+/// A LED with a second marker LED, which is lit during the display of a PM time
+///
+/// The marker shows the half of the day which gets lost by [`Format::Hour12`](morseclock::Format::Hour12).
+#[derive(Debug)]
+pub struct AmPmLed<L, M> {
+    /// The LED displaying the time
+    pub led: L,
+    /// The LED lit for PM and off for AM
+    pub marker: M,
+}
+
+impl<L: Led, M: Led> AmPmLed<L, M> {
+    pub fn new(led: L, marker: M) -> Self {
+        Self { led, marker }
+    }
+}
+
+impl<L: Led, M: Led> Led for AmPmLed<L, M> {
+    fn on(&mut self) -> anyhow::Result<()> {
+        self.led.on()
+    }
+
+    fn off(&mut self) -> anyhow::Result<()> {
+        self.led.off()
+    }
+
+    fn display(&mut self, symbol: Symbol, on: Duration, off: Duration) -> anyhow::Result<()> {
+        self.led.display(symbol, on, off)
+    }
+
+    fn start_cycle(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
+        if now.hour() >= 12 {
+            self.marker.on()?;
+        } else {
+            self.marker.off()?;
+        }
+
+        self.led.start_cycle(now)
+    }
+
+    fn end_cycle(&mut self) -> anyhow::Result<()> {
+        self.marker.off()?;
+        self.led.end_cycle()
+    }
+}
+
+/// The state of a [`SysfsLed`] during breaks, i.e. whenever no symbol is displayed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BreakState {
+    /// The LED is off and symbols are displayed as light pulses
+    #[default]
+    Off,
+    /// The LED is lit and symbols are displayed as dark pulses
+    On,
+    /// The LED is dimmed to a quarter of the on level and symbols are displayed as
+    /// light pulses
+    Dim,
+}
+
+impl str::FromStr for BreakState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(BreakState::Off),
+            "on" => Ok(BreakState::On),
+            "dim" => Ok(BreakState::Dim),
+            _ => Err(Error::InvalidBreakState),
+        }
+    }
+}
+
+/// A LED which calls a function with every displayed symbol and its durations
+///
+/// Useful to log the durations while tuning a [`Timing`](crate::Timing).
+pub struct Inspect<L, F> {
+    /// The LED displaying the symbols
+    pub led: L,
+    inspect: F,
+}
+
+impl<L: Led, F: FnMut(Symbol, Duration, Duration)> Inspect<L, F> {
+    /// Calls `inspect` with the symbol and its on and off durations before `led` displays it
+    pub fn new(led: L, inspect: F) -> Self {
+        Self { led, inspect }
+    }
+}
+
+impl<L: Led, F: FnMut(Symbol, Duration, Duration)> Led for Inspect<L, F> {
+    fn on(&mut self) -> anyhow::Result<()> {
+        self.led.on()
+    }
+
+    fn off(&mut self) -> anyhow::Result<()> {
+        self.led.off()
+    }
+
+    fn display(&mut self, symbol: Symbol, on: Duration, off: Duration) -> anyhow::Result<()> {
+        (self.inspect)(symbol, on, off);
+        self.led.display(symbol, on, off)
+    }
+
+    fn start_cycle(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
+        self.led.start_cycle(now)
+    }
+
+    fn end_cycle(&mut self) -> anyhow::Result<()> {
+        self.led.end_cycle()
+    }
+}
+
+/// Enforces a minimum interval between consecutive writes
+#[derive(Clone, Copy, Debug, Default)]
+struct Throttle {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl Throttle {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+        }
+    }
+
+    /// Returns how long to wait at `now` before writing and records the write
+    fn delay(&mut self, now: Instant) -> Duration {
+        let delay = self
+            .last
+            .map(|last| (last + self.interval).saturating_duration_since(now))
+            .unwrap_or_default();
+
+        self.last = Some(now + delay);
+        delay
+    }
+}
+
+/// The paths of the attribute files of a sysfs LED
+struct Paths {
+    brightness: path::PathBuf,
+    trigger: path::PathBuf,
+    max_brightness: path::PathBuf,
+}
+
+impl Paths {
+    fn new(dir: &path::Path) -> Self {
+        Self {
+            brightness: dir.join("brightness"),
+            trigger: dir.join("trigger"),
+            max_brightness: dir.join("max_brightness"),
+        }
+    }
+}
+
+/// Reads the number in the attribute file `path`
+///
+/// Some drivers surround the number by whitespace, empty lines or a byte order mark.
+fn read_number(path: &path::Path) -> anyhow::Result<u32> {
+    let raw = fs::read_to_string(path)?;
+
+    raw.trim_start_matches('\u{feff}')
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid number {:?} in {}", raw, path.display()))
+}
+
+/// Fails with [`Error::NotWritable`] unless the attribute file `path` can be written
+fn check_writable(path: &path::Path) -> anyhow::Result<()> {
+    let not_writable = || Error::NotWritable(path.to_owned());
+
+    if fs::metadata(path)?.permissions().readonly() {
+        return Err(not_writable().into());
+    }
+
+    match fs::OpenOptions::new().write(true).open(path) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => Err(not_writable().into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A LED exposed by the kernel via `/sys/class/leds`
+///
+/// On construction the trigger of the LED is set to `none`; the previous trigger
+/// and brightness are restored on drop. Both can be changed by a [`SysfsLedBuilder`].
+#[derive(Debug)]
+pub struct SysfsLed {
+    max_brightness: u32,
+    on_level: u32,
+    break_state: BreakState,
+    duty_brightness: bool,
+    /// The brightness during the pause between two cycles, `None` for the break state
+    idle_level: Option<u32>,
+    old_brightness: u32,
+    /// The last written brightness, `None` before the first and after a failed write
+    brightness: Option<u32>,
+    /// The trigger to restore, `None` to leave the trigger as it is
+    trigger: Option<String>,
+    restores_brightness: bool,
+    /// Whether a newline is appended to every written brightness
+    newline: bool,
+    brightness_path: path::PathBuf,
+    brightness_file: fs::File,
+    trigger_file: fs::File,
+    throttle: Throttle,
+    restored: bool,
+    max_brightness_path: Option<path::PathBuf>,
+}
+
+/// A builder for a [`SysfsLed`] with non-default options
+#[derive(Clone, Debug)]
+pub struct SysfsLedBuilder {
+    path: path::PathBuf,
+    min_write_interval: Duration,
+    proportional_restore: bool,
+    restore_trigger: bool,
+    restore_brightness: bool,
+    force_trigger: Option<String>,
+    newline: bool,
+}
+
+impl SysfsLedBuilder {
+    /// Enforces a minimum interval between two consecutive brightness writes
+    ///
+    /// Some slow bus-connected LED controllers drop updates which are written too fast.
+    /// Writes happening earlier are delayed until the interval has passed.
+    pub fn min_write_interval(mut self, interval: Duration) -> Self {
+        self.min_write_interval = interval;
+        self
+    }
+
+    /// Restores the previous brightness relative to the maximum brightness at restore time
+    ///
+    /// The previous brightness is kept as a fraction of the maximum brightness read on
+    /// construction. If `max_brightness` has changed in the meantime, e.g. by a firmware
+    /// update of the controller, the same fraction of the new maximum is restored.
+    pub fn proportional_restore(mut self, proportional: bool) -> Self {
+        self.proportional_restore = proportional;
+        self
+    }
+
+    /// Restores the previous trigger when the LED is closed or dropped, defaults to `true`
+    pub fn restore_trigger(mut self, restore: bool) -> Self {
+        self.restore_trigger = restore;
+        self
+    }
+
+    /// Restores the previous brightness when the LED is closed or dropped, defaults to `true`
+    pub fn restore_brightness(mut self, restore: bool) -> Self {
+        self.restore_brightness = restore;
+        self
+    }
+
+    /// The trigger set on construction, defaults to `none`
+    ///
+    /// With `None` the trigger is left as it is. A trigger other than `none` keeps
+    /// changing the brightness on its own.
+    pub fn force_trigger(mut self, trigger: Option<&str>) -> Self {
+        self.force_trigger = trigger.map(str::to_owned);
+        self
+    }
+
+    /// Appends a newline to every written brightness, defaults to `false`
+    ///
+    /// Most drivers accept the value either way, a few only parse it with a newline.
+    pub fn newline(mut self, newline: bool) -> Self {
+        self.newline = newline;
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<SysfsLed> {
+        let paths = Paths::new(&self.path);
+
+        let trigger = fs::read_to_string(&paths.trigger)?;
+        let max_brightness = read_number(&paths.max_brightness)?;
+        if max_brightness == 0 {
+            // the LED couldn't be switched on at all
+            return Err(Error::ZeroMaxBrightness(paths.max_brightness).into());
+        }
+        let old_brightness = read_number(&paths.brightness)?;
+
+        check_writable(&paths.brightness)?;
+        check_writable(&paths.trigger)?;
+
+        let mut trigger_file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&paths.trigger)?;
+
+        if let Some(forced) = &self.force_trigger {
+            SysfsLed::write_trigger(&mut trigger_file, forced)?;
+        }
+
+        log::debug!(
+            "Opened the LED {}: brightness {} of {}, trigger {:?}",
+            self.path.display(),
+            old_brightness,
+            max_brightness,
+            parser::parse_trigger(&trigger).unwrap_or("none"),
+        );
+
+        // without an active trigger, "none" only has to be restored after forcing another one
+        let trigger = match parser::parse_trigger(&trigger) {
+            _ if !self.restore_trigger => None,
+            Some(trigger) => Some(trigger.to_owned()),
+            None => self
+                .force_trigger
+                .filter(|forced| forced != "none")
+                .map(|_| "none".to_owned()),
+        };
+
+        Ok(SysfsLed {
+            max_brightness,
+            on_level: max_brightness,
+            break_state: BreakState::Off,
+            duty_brightness: false,
+            idle_level: None,
+            old_brightness,
+            brightness: None,
+            trigger,
+            restores_brightness: self.restore_brightness,
+            newline: self.newline,
+            brightness_file: fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&paths.brightness)?,
+            brightness_path: paths.brightness,
+            trigger_file,
+            throttle: Throttle::new(self.min_write_interval),
+            restored: false,
+            max_brightness_path: self.proportional_restore.then_some(paths.max_brightness),
+        })
+    }
+}
+
+impl SysfsLed {
+    pub fn new<P: AsRef<path::Path>>(path: P) -> anyhow::Result<Self> {
+        Self::builder(path).build()
+    }
+
+    /// Creates a builder for a LED in the sysfs directory `path`
+    pub fn builder<P: AsRef<path::Path>>(path: P) -> SysfsLedBuilder {
+        SysfsLedBuilder {
+            path: path.as_ref().to_owned(),
+            min_write_interval: Duration::ZERO,
+            proportional_restore: false,
+            restore_trigger: true,
+            restore_brightness: true,
+            force_trigger: Some("none".to_owned()),
+            newline: false,
+        }
+    }
+
+    /// Describes the state of the LED in the sysfs directory `path`, without changing it
+    ///
+    /// Lists the maximum and current brightness, the triggers and whether the attribute
+    /// files are writable. Attributes which can't be read are described by their error.
+    pub fn diagnose<P: AsRef<path::Path>>(path: P) -> String {
+        let paths = Paths::new(path.as_ref());
+        let read = |path: &path::Path| {
+            fs::read_to_string(path)
+                .map(|s| s.trim().to_owned())
+                .unwrap_or_else(|e| format!("error: {}", e))
+        };
+        let writable = |path: &path::Path| match fs::OpenOptions::new().write(true).open(path) {
+            Ok(_) => "yes".to_owned(),
+            Err(e) => format!("no ({})", e),
+        };
+
+        let triggers = read(&paths.trigger);
+        let active = parser::parse_trigger(&triggers).unwrap_or("none");
+
+        format!(
+            "max_brightness: {}\n\
+             brightness: {}\n\
+             active trigger: {}\n\
+             available triggers: {}\n\
+             brightness writable: {}\n\
+             trigger writable: {}\n",
+            read(&paths.max_brightness),
+            read(&paths.brightness),
+            active,
+            triggers,
+            writable(&paths.brightness),
+            writable(&paths.trigger),
+        )
+    }
+
+    /// The maximum brightness supported by the LED
+    pub fn max_brightness(&self) -> u32 {
+        self.max_brightness
+    }
+
+    /// Sets the brightness used by [`Led::on`], clamped to the maximum brightness
+    ///
+    /// Defaults to the maximum brightness.
+    pub fn set_on_level(&mut self, level: u32) {
+        self.on_level = level.min(self.max_brightness);
+    }
+
+    /// Sets the state of the LED during breaks
+    ///
+    /// Defaults to [`BreakState::Off`].
+    pub fn set_break_state(&mut self, state: BreakState) {
+        self.break_state = state;
+    }
+
+    /// Displays the duty cycle of a symbol as brightness instead of time
+    ///
+    /// The LED is kept on for most of the duration of a symbol, at the fraction of the
+    /// on level given by its duty cycle. The last seventh of a symbol, as long as a break
+    /// within a character, is a gap in the break state, so consecutive symbols can still
+    /// be counted. LEDs which can only be switched on and off, with a maximum brightness
+    /// of 1, keep blinking with the duty cycle. Defaults to `false`.
+    pub fn set_duty_brightness(&mut self, duty_brightness: bool) {
+        self.duty_brightness = duty_brightness;
+    }
+
+    /// Lets the LED glow at `fraction` of the maximum brightness between two cycles
+    ///
+    /// Shows that the clock is alive during a long pause. The LED is dimmed after the
+    /// last symbol of a time and returns to the break state before the next one, so the
+    /// breaks within a time aren't affected. Defaults to `None`, keeping the break state.
+    pub fn set_idle_fraction(&mut self, fraction: Option<f64>) {
+        self.idle_level = fraction.map(|fraction| self.fraction_level(fraction));
+    }
+
+    /// The brightness held for a symbol with the durations `on` and `off`, if the duty
+    /// cycle is displayed as brightness
+    fn duty_level(&self, on: Duration, off: Duration) -> Option<u32> {
+        if !self.duty_brightness || self.max_brightness <= 1 {
+            return None;
+        }
+
+        let total = (on + off).as_secs_f64();
+        let duty = if total > 0.0 {
+            on.as_secs_f64() / total
+        } else {
+            1.0
+        };
+
+        Some(self.fraction_level(duty * f64::from(self.on_level) / f64::from(self.max_brightness)))
+    }
+
+    /// The brightness levels a blink is displayed with in duty-brightness mode, with how long each is held
+    ///
+    /// The duty level is followed by a gap in the break state, so consecutive blinks stay apart.
+    /// `None` if the blink is displayed by switching the LED on and off.
+    fn duty_steps(
+        &self,
+        symbol: Symbol,
+        on: Duration,
+        off: Duration,
+    ) -> Option<[(u32, Duration); 2]> {
+        if !matches!(symbol, Symbol::Short | Symbol::Long) {
+            return None;
+        }
+
+        let level = self.duty_level(on, off)?;
+        // the duration of a symbol break, see `Timing::durations`
+        let gap = (on + off) / 7;
+
+        Some([(level, on + off - gap), (self.levels().1, gap)])
+    }
+
+    /// The brightness of `fraction` of the maximum brightness, clamped to the valid range
+    fn fraction_level(&self, fraction: f64) -> u32 {
+        // NaN is mapped to 0 by the saturating cast
+        (fraction.clamp(0.0, 1.0) * f64::from(self.max_brightness)).round() as u32
+    }
+
+    /// The brightness of the LED while displaying a symbol and during a break
+    fn levels(&self) -> (u32, u32) {
+        match self.break_state {
+            BreakState::Off => (self.on_level, 0),
+            BreakState::On => (0, self.on_level),
+            BreakState::Dim => (self.on_level, self.on_level / 4),
+        }
+    }
+
+    /// Sets the brightness, unless it has already been written by the last write
+    ///
+    /// `value` is clamped to the maximum brightness, which some drivers reject exceeding.
+    pub fn set(&mut self, value: u32) -> anyhow::Result<()> {
+        if self.brightness == Some(value.min(self.max_brightness)) {
+            return Ok(());
+        }
+
+        self.force_set(value)
+    }
+
+    /// Writes the brightness, even if it has already been written by the last write
+    ///
+    /// Use this after something else may have changed the brightness of the LED.
+    /// `value` is clamped to the maximum brightness like by [`set`](Self::set).
+    pub fn force_set(&mut self, value: u32) -> anyhow::Result<()> {
+        let value = value.min(self.max_brightness);
+        let delay = self.throttle.delay(Instant::now());
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+
+        self.brightness = None;
+        let raw = if self.newline {
+            format!("{}\n", value)
+        } else {
+            value.to_string()
+        };
+        Self::write_at_start(&mut self.brightness_file, raw.as_bytes()).with_context(|| {
+            format!(
+                "Writing {} to {} failed",
+                value,
+                self.brightness_path.display()
+            )
+        })?;
+        self.brightness = Some(value);
+
+        Ok(())
+    }
+
+    /// Sets the brightness to `fraction` of the maximum brightness
+    ///
+    /// `fraction` is clamped to the range from 0 to 1 and rounded to the nearest
+    /// brightness value.
+    pub fn set_fraction(&mut self, fraction: f64) -> anyhow::Result<()> {
+        self.set(self.fraction_level(fraction))
+    }
+
+    /// Fades from the current brightness to `target` over `duration`
+    ///
+    /// The brightness follows a gamma corrected curve, so the perceived brightness
+    /// changes evenly. Unlike [`set`](Self::set), [`Led::on`] and [`Led::off`], which switch
+    /// the brightness at once, the intermediate values are written as well. `target` is
+    /// clamped to the maximum brightness.
+    pub fn fade_to(&mut self, target: u32, duration: Duration) -> anyhow::Result<()> {
+        let steps = fade_steps(
+            self.brightness.unwrap_or(self.old_brightness),
+            target,
+            self.max_brightness,
+        );
+        let interval = duration / steps.len().max(1) as u32;
+
+        for value in steps {
+            self.set(value)?;
+            thread::sleep(interval);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the contents of `file` with `buf` using a single positioned write
+    ///
+    /// The file is truncated to the length of `buf` so a shorter value doesn't leave the
+    /// residue of a longer one behind in regular files. sysfs ignores the truncation.
+    #[cfg(unix)]
+    fn write_at_start(file: &mut fs::File, buf: &[u8]) -> anyhow::Result<()> {
+        use std::os::unix::fs::FileExt;
+
+        file.write_all_at(buf, 0)?;
+        file.set_len(buf.len() as u64)?;
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn write_at_start(file: &mut fs::File, buf: &[u8]) -> anyhow::Result<()> {
+        use std::io::{Seek, SeekFrom};
+
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(buf)?;
+        file.set_len(buf.len() as u64)?;
+
+        Ok(())
+    }
+
+    fn write_trigger(file: &mut fs::File, trigger: &str) -> anyhow::Result<()> {
+        Self::write_at_start(file, trigger.as_bytes())
+    }
+
+    fn reset_trigger(&mut self) -> anyhow::Result<()> {
+        if let Some(trigger) = &self.trigger {
+            Self::write_trigger(&mut self.trigger_file, trigger)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Restores the previous brightness and trigger and closes the LED
+    ///
+    /// Unlike dropping the LED, this returns the errors which happen during the restore.
+    pub fn close(mut self) -> anyhow::Result<()> {
+        self.restore()
+    }
+
+    /// Restores the previous brightness and trigger, keeping the LED open
+    ///
+    /// Unlike dropping the LED, this returns the errors which happen during the restore.
+    /// The LED is restored only once: later calls and dropping the LED do nothing, even
+    /// if the LED has been written to in between, and even if the restore failed.
+    pub fn restore(&mut self) -> anyhow::Result<()> {
+        if self.restored {
+            return Ok(());
+        }
+
+        self.restored = true;
+        log::debug!(
+            "Restoring the LED: brightness {:?}, trigger {:?}",
+            self.restores_brightness.then_some(self.old_brightness),
+            self.trigger,
+        );
+
+        let brightness = if self.restores_brightness {
+            self.restore_brightness()
+                .and_then(|brightness| self.force_set(brightness))
+        } else {
+            Ok(())
+        };
+        let trigger = self.reset_trigger();
+
+        brightness.and(trigger)
+    }
+
+    /// The brightness to restore, see [`SysfsLedBuilder::proportional_restore`]
+    fn restore_brightness(&self) -> anyhow::Result<u32> {
+        let path = match &self.max_brightness_path {
+            Some(path) if self.max_brightness != 0 => path,
+            _ => return Ok(self.old_brightness),
+        };
+
+        let max_brightness = u64::from(read_number(path)?);
+        let old_max = u64::from(self.max_brightness);
+        let scaled = (u64::from(self.old_brightness) * max_brightness + old_max / 2) / old_max;
+
+        Ok(scaled.min(max_brightness).try_into()?)
+    }
+}
+
+/// The gamma used by [`SysfsLed::fade_to`] to convert perceived into written brightness
+const GAMMA: f64 = 2.2;
+
+/// The brightness values written when fading from `from` to `to`, excluding `from`
+///
+/// The perceived brightness changes in even steps, at most one per brightness value but
+/// no more than 256 over the whole range. Repeated values are written only once.
+fn fade_steps(from: u32, to: u32, max_brightness: u32) -> Vec<u32> {
+    let to = to.min(max_brightness);
+    if from == to {
+        return Vec::new();
+    }
+
+    let max = f64::from(max_brightness);
+    let perceived = |value: u32| (f64::from(value.min(max_brightness)) / max).powf(GAMMA.recip());
+    let (start, end) = (perceived(from), perceived(to));
+    let steps = ((end - start).abs() * max.min(256.0)).ceil().max(1.0) as u32;
+
+    let mut values: Vec<u32> = (1..steps)
+        .map(|step| {
+            let level = start + (end - start) * f64::from(step) / f64::from(steps);
+            (level.powf(GAMMA) * max).round() as u32
+        })
+        .chain([to])
+        .collect();
+    values.dedup();
+    values.retain(|&value| value != from);
+
+    values
+}
+
+impl Led for SysfsLed {
+    fn on(&mut self) -> anyhow::Result<()> {
+        self.set(self.levels().0)
+    }
+
+    fn off(&mut self) -> anyhow::Result<()> {
+        self.set(self.levels().1)
+    }
+
+    fn display(&mut self, symbol: Symbol, on: Duration, off: Duration) -> anyhow::Result<()> {
+        match self.duty_steps(symbol, on, off) {
+            Some(steps) => {
+                for (level, duration) in steps {
+                    self.set(level)?;
+                    thread::sleep(duration);
+                }
+
+                Ok(())
+            }
+            None => blink(self, symbol, on, off),
+        }
+    }
+
+    fn start_cycle(&mut self, _now: NaiveDateTime) -> anyhow::Result<()> {
+        match self.idle_level {
+            Some(_) => self.off(),
+            None => Ok(()),
+        }
+    }
+
+    fn end_cycle(&mut self) -> anyhow::Result<()> {
+        match self.idle_level {
+            Some(level) => self.set(level),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for SysfsLed {
+    /// Restores the LED on a best-effort basis
+    ///
+    /// A failing restore, e.g. of a LED which has been unplugged, is only logged as a
+    /// warning: a panic while unwinding would abort and hide the original error. Use
+    /// [`SysfsLed::close`] to handle the error.
+    fn drop(&mut self) {
+        log::trace!("Dropping the LED");
+
+        if let Err(e) = self.restore() {
+            log::warn!("Failed to restore the LED: {:#}", e);
+        }
+    }
+}
+
+/// A color of a [`RgbSysfsLed`], parsed from a hex string like `ff8000`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl str::FromStr for Rgb {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(Error::InvalidColor);
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+        match (channel(0), channel(2), channel(4)) {
+            (Ok(r), Ok(g), Ok(b)) => Ok(Rgb(r, g, b)),
+            _ => Err(Error::InvalidColor),
+        }
+    }
+}
+
+/// A RGB LED made up of three sysfs LEDs, lighting shorts and longs in different colors
+///
+/// Each channel is scaled to its own maximum brightness. Every channel restores its
+/// previous state on drop like a [`SysfsLed`].
+#[derive(Debug)]
+pub struct RgbSysfsLed {
+    channels: [SysfsLed; 3],
+    short: Rgb,
+    long: Rgb,
+    /// The color lit by [`Led::on`]
+    current: Rgb,
+}
+
+impl RgbSysfsLed {
+    /// Creates a RGB LED of the sysfs LEDs in the directories `red`, `green` and `blue`
+    ///
+    /// Shorts are lit green and longs red by default.
+    pub fn new<P: AsRef<path::Path>>([red, green, blue]: [P; 3]) -> anyhow::Result<Self> {
+        Ok(Self::from_channels([
+            SysfsLed::new(red)?,
+            SysfsLed::new(green)?,
+            SysfsLed::new(blue)?,
+        ]))
+    }
+
+    /// Creates a RGB LED of the red, green and blue `channels`
+    pub fn from_channels(channels: [SysfsLed; 3]) -> Self {
+        let long = Rgb(0xff, 0, 0);
+
+        Self {
+            channels,
+            short: Rgb(0, 0xff, 0),
+            long,
+            current: long,
+        }
+    }
+
+    /// Sets the color of `symbol`; only shorts and longs are lit
+    pub fn set_color(&mut self, symbol: Symbol, color: Rgb) {
+        match symbol {
+            Symbol::Short => self.short = color,
+            Symbol::Long => {
+                self.long = color;
+                self.current = color;
+            }
+            Symbol::Break(_) | Symbol::Meridiem(_) => {}
+        }
+    }
+
+    /// The color lit for `symbol`
+    fn color(&self, symbol: Symbol) -> Rgb {
+        match symbol {
+            Symbol::Short => self.short,
+            _ => self.long,
+        }
+    }
+
+    /// Lights `color`, scaling every channel to its maximum brightness
+    pub fn set(&mut self, color: Rgb) -> anyhow::Result<()> {
+        let Rgb(r, g, b) = color;
+
+        for (channel, value) in self.channels.iter_mut().zip([r, g, b]) {
+            let max = u64::from(channel.max_brightness());
+            let scaled = (u64::from(value) * max + 127) / 255;
+            channel.set(scaled.try_into()?)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Led for RgbSysfsLed {
+    fn on(&mut self) -> anyhow::Result<()> {
+        self.set(self.current)
+    }
+
+    fn off(&mut self) -> anyhow::Result<()> {
+        self.set(Rgb::default())
+    }
+
+    fn display(&mut self, symbol: Symbol, on: Duration, off: Duration) -> anyhow::Result<()> {
+        self.current = self.color(symbol);
+        let result = blink(self, symbol, on, off);
+        self.current = self.long;
+
+        result
+    }
+}
+
+/// A "LED" which writes the displayed symbols to a named pipe for an external driver
+///
+/// Every symbol is written as a single line `<symbol> <on_ms> <off_ms>`, where `<symbol>`
+/// is one of `short`, `long`, `break`, `am` or `pm`. Switching the LED on or off outside of a
+/// symbol is written as a line `on` or `off`. Each line is flushed immediately.
+#[derive(Debug)]
+pub struct FifoLed<W = fs::File> {
+    writer: W,
+}
+
+impl FifoLed {
+    /// Opens the named pipe at `path` for writing
+    pub fn open<P: AsRef<path::Path>>(path: P) -> anyhow::Result<Self> {
+        Ok(Self::new(fs::OpenOptions::new().write(true).open(path)?))
+    }
+}
+
+impl<W: Write> FifoLed<W> {
+    /// Creates a new FIFO backend writing to `writer`
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_line(&mut self, args: std::fmt::Arguments<'_>) -> anyhow::Result<()> {
+        self.writer.write_fmt(args)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Led for FifoLed<W> {
+    fn on(&mut self) -> anyhow::Result<()> {
+        self.write_line(format_args!("on"))
+    }
+
+    fn off(&mut self) -> anyhow::Result<()> {
+        self.write_line(format_args!("off"))
+    }
+
+    fn display(&mut self, symbol: Symbol, on: Duration, off: Duration) -> anyhow::Result<()> {
+        let name = match symbol {
+            Symbol::Break(_) => "break",
+            Symbol::Short => "short",
+            Symbol::Long => "long",
+            Symbol::Meridiem(Meridiem::Am) => "am",
+            Symbol::Meridiem(Meridiem::Pm) => "pm",
+        };
+
+        self.write_line(format_args!(
+            "{} {} {}",
+            name,
+            on.as_millis(),
+            off.as_millis()
+        ))?;
+        thread::sleep(on + off);
+
+        Ok(())
+    }
+}
+
+/// A simulated LED drawn on a terminal, for running without hardware
+///
+/// The LED is drawn as `●` while on and `○` while off, overwriting the same line.
+#[derive(Debug)]
+pub struct SimLed<W = std::io::Stdout> {
+    writer: W,
+}
+
+impl SimLed {
+    /// Creates a simulated LED drawn on stdout
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+}
+
+impl<W: Write> SimLed<W> {
+    /// Creates a simulated LED drawn on `writer`
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn draw(&mut self, glyph: &str) -> anyhow::Result<()> {
+        write!(self.writer, "\r{}", glyph)?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Led for SimLed<W> {
+    fn on(&mut self) -> anyhow::Result<()> {
+        self.draw("●")
+    }
+
+    fn off(&mut self) -> anyhow::Result<()> {
+        self.draw("○")
+    }
+}
+
+/// A LED connected to a GPIO pin, driven through [`embedded_hal`]
+///
+/// The pin is set high to switch the LED on. Unlike a [`SysfsLed`], nothing is restored
+/// when the LED is dropped.
+#[cfg(feature = "embedded-hal")]
+#[derive(Debug)]
+pub struct PinLed<P> {
+    pin: P,
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<P: embedded_hal::digital::OutputPin> PinLed<P> {
+    /// Creates a new LED driving `pin`
+    pub fn new(pin: P) -> Self {
+        Self { pin }
+    }
+
+    /// Returns the underlying pin
+    pub fn into_inner(self) -> P {
+        self.pin
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<P: embedded_hal::digital::OutputPin> Led for PinLed<P> {
+    fn on(&mut self) -> anyhow::Result<()> {
+        self.pin
+            .set_high()
+            .map_err(|e| anyhow::anyhow!("Can't set pin high: {:?}", e))
+    }
+
+    fn off(&mut self) -> anyhow::Result<()> {
+        self.pin
+            .set_low()
+            .map_err(|e| anyhow::anyhow!("Can't set pin low: {:?}", e))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::tests::StopAfter;
+    use crate::{DutyCycle, Pause, Timing};
+    use morseclock::{BreakKind, Format};
+    use std::sync::atomic::AtomicBool;
+
+    /// Creates a fake sysfs LED directory
+    pub fn fake_led(max_brightness: &str, brightness: &str, trigger: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+
+        fs::write(dir.path().join("max_brightness"), max_brightness).unwrap();
+        fs::write(dir.path().join("brightness"), brightness).unwrap();
+        fs::write(dir.path().join("trigger"), trigger).unwrap();
+
+        dir
+    }
+
+    fn read(dir: &tempfile::TempDir, file: &str) -> String {
+        fs::read_to_string(dir.path().join(file)).unwrap()
+    }
+
+    #[test]
+    fn sysfs_set() {
+        let dir = fake_led("255\n", "0\n", "none [timer] heartbeat\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        for value in [1, 2, 3, 40, 50, 255] {
+            led.set(value).unwrap();
+            assert_eq!(read(&dir, "brightness"), value.to_string());
+        }
+
+        assert_eq!(read(&dir, "brightness"), "255");
+    }
+
+    #[test]
+    fn sysfs_set_clamped() {
+        let dir = fake_led("100\n", "0\n", "[none]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        led.set(1000).unwrap();
+        assert_eq!(read(&dir, "brightness"), "100");
+
+        // already written after clamping
+        fs::write(dir.path().join("brightness"), "0").unwrap();
+        led.set(100).unwrap();
+        assert_eq!(read(&dir, "brightness"), "0");
+
+        led.force_set(u32::MAX).unwrap();
+        assert_eq!(read(&dir, "brightness"), "100");
+    }
+
+    #[test]
+    fn sysfs_newline() {
+        let dir = fake_led("255\n", "0\n", "[none]\n");
+        let mut led = SysfsLed::builder(&dir).newline(true).build().unwrap();
+
+        for value in [255, 42, 0] {
+            led.set(value).unwrap();
+            assert_eq!(read(&dir, "brightness"), format!("{}\n", value));
+        }
+
+        let mut led = SysfsLed::new(&dir).unwrap();
+        led.set(7).unwrap();
+        assert_eq!(read(&dir, "brightness"), "7");
+    }
+
+    #[test]
+    fn sysfs_write_error() {
+        let dir = fake_led("255\n", "0\n", "[none]\n");
+        let brightness = dir.path().join("brightness");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        // a file which can't be written, as a stand-in for a driver rejecting the value
+        led.brightness_file = fs::File::open(&brightness).unwrap();
+
+        let error = format!("{:#}", led.set(42).unwrap_err());
+        assert!(
+            error.starts_with(&format!("Writing 42 to {} failed: ", brightness.display())),
+            "{}",
+            error
+        );
+        assert_eq!(led.brightness, None);
+
+        led.brightness_file = fs::OpenOptions::new()
+            .write(true)
+            .open(&brightness)
+            .unwrap();
+        led.close().unwrap();
+    }
+
+    #[test]
+    fn sysfs_set_shorter() {
+        let dir = fake_led("255\n", "100\n", "[none]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        for value in [255, 100, 42, 5, 0] {
+            led.set(value).unwrap();
+            assert_eq!(read(&dir, "brightness"), value.to_string());
+        }
+
+        drop(led);
+        assert_eq!(read(&dir, "brightness"), "100");
+    }
+
+    #[test]
+    fn sysfs_fade() {
+        let dir = fake_led("255\n", "0\n", "[none]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        led.fade_to(200, Duration::ZERO).unwrap();
+        assert_eq!(read(&dir, "brightness"), "200");
+        led.fade_to(1000, Duration::ZERO).unwrap();
+        assert_eq!(read(&dir, "brightness"), "255");
+        led.set(10).unwrap();
+        led.fade_to(0, Duration::ZERO).unwrap();
+        assert_eq!(read(&dir, "brightness"), "0");
+    }
+
+    #[test]
+    fn fade_steps_monotonic() {
+        for (from, to, max) in [(0, 255, 255), (255, 0, 255), (3, 7, 10), (0, 4095, 4095)] {
+            let steps = fade_steps(from, to, max);
+            let values = [from].into_iter().chain(steps.iter().copied());
+
+            assert_eq!(steps.last(), Some(&to));
+            assert!(steps.len() <= max.min(256) as usize);
+            if from < to {
+                assert!(values.is_sorted_by(|a, b| a < b), "{:?}", steps);
+            } else {
+                assert!(values.is_sorted_by(|a, b| a > b), "{:?}", steps);
+            }
+        }
+
+        let steps = fade_steps(0, 255, 255);
+        // the gamma curve spends more steps on the dark end than a linear one
+        let dark = steps.iter().filter(|&&value| value < 255 / 4).count();
+        assert!(dark > steps.len() / 3, "{:?}", steps);
+        assert!(fade_steps(42, 42, 255).is_empty());
+        assert_eq!(fade_steps(0, 1, 1), [1]);
+    }
+
+    #[test]
+    fn sysfs_fraction() {
+        for (max_brightness, levels) in [
+            ("1\n", ["0", "0", "1", "1", "1", "0"]),
+            ("255\n", ["0", "77", "128", "255", "255", "0"]),
+            ("4095\n", ["0", "1229", "2048", "4095", "4095", "0"]),
+        ] {
+            let dir = fake_led(max_brightness, "0\n", "[none]\n");
+            let mut led = SysfsLed::new(&dir).unwrap();
+
+            for (fraction, level) in [0.0, 0.3, 0.5, 1.0, 7.0, -1.0].into_iter().zip(levels) {
+                led.set_fraction(fraction).unwrap();
+                assert_eq!(read(&dir, "brightness"), level, "{}", fraction);
+            }
+
+            led.set_fraction(f64::NAN).unwrap();
+            assert_eq!(read(&dir, "brightness"), "0");
+        }
+    }
+
+    #[test]
+    fn sysfs_idle_fraction() {
+        let dir = fake_led("200\n", "7\n", "[none]\n");
+        let brightness = dir.path().join("brightness");
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(4, 45, 2, &running);
+
+        let mut led = SysfsLed::new(&dir).unwrap();
+        led.set_idle_fraction(Some(0.05));
+
+        // the idle brightness is never seen while a time is displayed
+        let mut seen = Vec::new();
+        let mut inspected = Inspect::new(led, |_, _, _| {
+            seen.push(fs::read_to_string(&brightness).unwrap())
+        });
+        crate::run(
+            &mut inspected,
+            &crate::tests::ZERO,
+            Format::Hour12,
+            &time,
+            &running,
+        )
+        .unwrap();
+        let led = inspected.led;
+
+        // "--.=----" twice, the second cycle starts after the first pause
+        assert_eq!(seen.len(), 2 * 8);
+        assert!(seen.iter().all(|level| level == "0"));
+        assert_eq!(read(&dir, "brightness"), "10");
+
+        drop(led);
+        assert_eq!(read(&dir, "brightness"), "7");
+    }
+
+    #[test]
+    fn sysfs_duty_brightness() {
+        let on = Duration::from_millis(30);
+        let off = Duration::from_millis(70);
+
+        for (max_brightness, level) in [("1\n", None), ("255\n", Some(77)), ("4095\n", Some(1229))]
+        {
+            let dir = fake_led(max_brightness, "0\n", "[none]\n");
+            let mut led = SysfsLed::new(&dir).unwrap();
+
+            assert_eq!(led.duty_level(on, off), None);
+            led.set_duty_brightness(true);
+            assert_eq!(led.duty_level(on, off), level, "{}", max_brightness);
+
+            led.display(Symbol::Short, Duration::ZERO, Duration::ZERO)
+                .unwrap();
+            assert_eq!(read(&dir, "brightness"), "0");
+        }
+
+        let dir = fake_led("255\n", "0\n", "[none]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+        led.set_duty_brightness(true);
+        led.set_on_level(100);
+        assert_eq!(led.duty_level(on, off), Some(30));
+        assert_eq!(led.duty_level(Duration::ZERO, Duration::ZERO), Some(100));
+    }
+
+    #[test]
+    fn sysfs_duty_brightness_gap() {
+        let dir = fake_led("255\n", "0\n", "[none]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+        let (on, off) = (Duration::from_millis(84), Duration::from_millis(56));
+        let ms = Duration::from_millis;
+
+        assert_eq!(led.duty_steps(Symbol::Long, on, off), None);
+        led.set_duty_brightness(true);
+        assert_eq!(
+            led.duty_steps(Symbol::Break(BreakKind::Symbol), on, off),
+            None
+        );
+
+        // two longs in a row are told apart by a gap of 20 ms
+        let steps =
+            [Symbol::Long, Symbol::Long].map(|symbol| led.duty_steps(symbol, on, off).unwrap());
+        assert_eq!(
+            steps.concat(),
+            [(153, ms(120)), (0, ms(20)), (153, ms(120)), (0, ms(20))]
+        );
+
+        led.set_break_state(BreakState::Dim);
+        assert_eq!(
+            led.duty_steps(Symbol::Short, on, off),
+            Some([(153, ms(120)), (63, ms(20))])
+        );
+
+        led.display(Symbol::Long, Duration::ZERO, Duration::ZERO)
+            .unwrap();
+        assert_eq!(read(&dir, "brightness"), "63");
+    }
+
+    #[test]
+    fn sysfs_cached() {
+        let dir = fake_led("255\n", "0\n", "[none]\n");
+        let brightness = dir.path().join("brightness");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        led.set(5).unwrap();
+        fs::write(&brightness, "changed").unwrap();
+        // the repeated value isn't written again
+        led.set(5).unwrap();
+        assert_eq!(read(&dir, "brightness"), "changed");
+
+        led.force_set(5).unwrap();
+        assert_eq!(read(&dir, "brightness"), "5");
+        led.set(6).unwrap();
+        assert_eq!(read(&dir, "brightness"), "6");
+
+        // a failed write invalidates the cache
+        let file = std::mem::replace(
+            &mut led.brightness_file,
+            fs::File::open(&brightness).unwrap(),
+        );
+        assert!(led.set(7).is_err());
+        led.brightness_file = file;
+        fs::write(&brightness, "changed").unwrap();
+        led.set(6).unwrap();
+        assert_eq!(read(&dir, "brightness"), "6");
+    }
+
+    #[test]
+    fn sysfs_whitespace() {
+        for max_brightness in [" 255 \n", "255\n\n", "\n255", "\u{feff}255\n"] {
+            let dir = fake_led(max_brightness, "0\n", "[none]\n");
+            let led = SysfsLed::new(&dir).unwrap();
+
+            assert_eq!(led.max_brightness(), 255, "{:?}", max_brightness);
+        }
+    }
+
+    #[test]
+    fn sysfs_invalid_number() {
+        for max_brightness in ["", "\n", "full\n"] {
+            let dir = fake_led(max_brightness, "0\n", "[none]\n");
+            let error = SysfsLed::new(&dir).unwrap_err().to_string();
+
+            assert!(
+                error.contains(&format!("{:?}", max_brightness)),
+                "{}",
+                error
+            );
+            assert!(error.contains("max_brightness"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn sysfs_zero_max_brightness() {
+        let dir = fake_led("0\n", "0\n", "none [timer]\n");
+
+        let error = SysfsLed::new(&dir).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::ZeroMaxBrightness(path)) if *path == dir.path().join("max_brightness")
+        ));
+        assert_eq!(read(&dir, "trigger"), "none [timer]\n");
+    }
+
+    #[test]
+    fn sysfs_not_writable() {
+        let dir = fake_led("255\n", "0\n", "none [timer]\n");
+        let brightness = dir.path().join("brightness");
+
+        let mut permissions = fs::metadata(&brightness).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&brightness, permissions).unwrap();
+
+        let error = SysfsLed::new(&dir).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::NotWritable(path)) if *path == brightness
+        ));
+        // the LED is left untouched
+        assert_eq!(read(&dir, "trigger"), "none [timer]\n");
+    }
+
+    #[test]
+    fn sysfs_on_level() {
+        let dir = fake_led("255\n", "0\n", "[none]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        led.on().unwrap();
+        assert_eq!(read(&dir, "brightness"), "255");
+
+        led.set_on_level(40);
+        led.on().unwrap();
+        assert_eq!(read(&dir, "brightness"), "40");
+        led.off().unwrap();
+        assert_eq!(read(&dir, "brightness"), "0");
+
+        led.set_on_level(1000);
+        led.on().unwrap();
+        assert_eq!(read(&dir, "brightness"), "255");
+    }
+
+    #[test]
+    fn sysfs_break_state() {
+        let dir = fake_led("255\n", "0\n", "[none]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+        led.set_on_level(200);
+
+        led.set_break_state(BreakState::On);
+        led.on().unwrap();
+        assert_eq!(read(&dir, "brightness"), "0");
+        // the LED stays lit after a symbol and during a break
+        led.display(Symbol::Short, Duration::ZERO, Duration::ZERO)
+            .unwrap();
+        led.display(
+            Symbol::Break(BreakKind::Group),
+            Duration::ZERO,
+            Duration::ZERO,
+        )
+        .unwrap();
+        assert_eq!(read(&dir, "brightness"), "200");
+
+        led.set_break_state(BreakState::Dim);
+        led.on().unwrap();
+        assert_eq!(read(&dir, "brightness"), "200");
+        led.off().unwrap();
+        assert_eq!(read(&dir, "brightness"), "50");
+
+        assert_eq!("dim".parse::<BreakState>().unwrap(), BreakState::Dim);
+        assert!("bright".parse::<BreakState>().is_err());
+    }
+
+    #[test]
+    fn sysfs_diagnose() {
+        let dir = fake_led("255\n", "12\n", "none [timer] heartbeat\n");
+
+        assert_eq!(
+            SysfsLed::diagnose(&dir),
+            "max_brightness: 255\n\
+             brightness: 12\n\
+             active trigger: timer\n\
+             available triggers: none [timer] heartbeat\n\
+             brightness writable: yes\n\
+             trigger writable: yes\n"
+        );
+        // diagnosing doesn't touch the LED
+        assert_eq!(read(&dir, "trigger"), "none [timer] heartbeat\n");
+    }
+
+    #[test]
+    fn sysfs_diagnose_missing() {
+        let dir = fake_led("255\n", "12\n", "[none]\n");
+        fs::remove_file(dir.path().join("max_brightness")).unwrap();
+
+        let diagnostics = SysfsLed::diagnose(&dir);
+        let lines: Vec<_> = diagnostics.lines().collect();
+
+        assert!(lines[0].starts_with("max_brightness: error: "));
+        assert_eq!(lines[2], "active trigger: none");
+    }
+
+    #[test]
+    fn sysfs_close() {
+        let dir = fake_led("255\n", "7\n", "none [timer]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        led.on().unwrap();
+        assert_eq!(read(&dir, "trigger"), "none");
+        led.close().unwrap();
+
+        assert_eq!(read(&dir, "brightness"), "7");
+        assert_eq!(read(&dir, "trigger"), "timer");
+    }
+
+    #[test]
+    fn sysfs_restore() {
+        let dir = fake_led("255\n", "7\n", "none [timer]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        led.on().unwrap();
+        assert_eq!(read(&dir, "brightness"), "255");
+        led.restore().unwrap();
+        assert_eq!(read(&dir, "brightness"), "7");
+        assert_eq!(read(&dir, "trigger"), "timer");
+
+        // neither a second restore nor the drop write anything
+        fs::write(dir.path().join("brightness"), "1").unwrap();
+        fs::write(dir.path().join("trigger"), "[heartbeat]").unwrap();
+        led.restore().unwrap();
+        drop(led);
+        assert_eq!(read(&dir, "brightness"), "1");
+        assert_eq!(read(&dir, "trigger"), "[heartbeat]");
+    }
+
+    #[test]
+    fn sysfs_close_error() {
+        let dir = fake_led("255\n", "7\n", "none [timer]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        // writing to a file opened read-only fails
+        led.brightness_file = fs::File::open(dir.path().join("brightness")).unwrap();
+
+        // the error is returned and dropping the LED doesn't restore (and panic) again
+        assert!(led.close().is_err());
+        assert_eq!(read(&dir, "trigger"), "timer");
+    }
+
+    #[test]
+    fn sysfs_drop_error() {
+        let dir = fake_led("255\n", "7\n", "none [timer]\n");
+        let mut led = SysfsLed::new(&dir).unwrap();
+
+        led.brightness_file = fs::File::open(dir.path().join("brightness")).unwrap();
+
+        // the failing restore doesn't panic and the trigger is still restored
+        drop(led);
+        assert_eq!(read(&dir, "trigger"), "timer");
+    }
+
+    #[test]
+    fn sysfs_builder_defaults() {
+        let dir = fake_led("255\n", "7\n", "none [timer]\n");
+        let mut led = SysfsLed::builder(&dir)
+            .restore_trigger(true)
+            .restore_brightness(true)
+            .force_trigger(Some("none"))
+            .build()
+            .unwrap();
+
+        assert_eq!(read(&dir, "trigger"), "none");
+        led.on().unwrap();
+        drop(led);
+        assert_eq!(read(&dir, "brightness"), "7");
+        assert_eq!(read(&dir, "trigger"), "timer");
+
+        // nothing is written to restore an inactive trigger
+        let dir = fake_led("255\n", "7\n", "[none] timer\n");
+        let led = SysfsLed::new(&dir).unwrap();
+        fs::write(dir.path().join("trigger"), "untouched").unwrap();
+        drop(led);
+        assert_eq!(read(&dir, "trigger"), "untouched");
+    }
+
+    #[test]
+    fn sysfs_builder_restore() {
+        let dir = fake_led("255\n", "7\n", "none [timer]\n");
+        let mut led = SysfsLed::builder(&dir)
+            .restore_trigger(false)
+            .build()
+            .unwrap();
+        led.on().unwrap();
+        drop(led);
+        assert_eq!(read(&dir, "brightness"), "7");
+        assert_eq!(read(&dir, "trigger"), "none");
+
+        let dir = fake_led("255\n", "7\n", "none [timer]\n");
+        let mut led = SysfsLed::builder(&dir)
+            .restore_brightness(false)
+            .build()
+            .unwrap();
+        led.on().unwrap();
+        led.close().unwrap();
+        assert_eq!(read(&dir, "brightness"), "255");
+        assert_eq!(read(&dir, "trigger"), "timer");
+    }
+
+    #[test]
+    fn sysfs_builder_force_trigger() {
+        let dir = fake_led("255\n", "7\n", "none [timer]\n");
+        let led = SysfsLed::builder(&dir).force_trigger(None).build().unwrap();
+        assert_eq!(read(&dir, "trigger"), "none [timer]\n");
+        drop(led);
+        assert_eq!(read(&dir, "trigger"), "timer");
+
+        let dir = fake_led("255\n", "7\n", "[none] heartbeat\n");
+        let led = SysfsLed::builder(&dir)
+            .force_trigger(Some("heartbeat"))
+            .build()
+            .unwrap();
+        assert_eq!(read(&dir, "trigger"), "heartbeat");
+        drop(led);
+        assert_eq!(read(&dir, "trigger"), "none");
+    }
+
+    #[test]
+    fn sysfs_proportional_restore() {
+        let open = |dir: &tempfile::TempDir, proportional| {
+            SysfsLed::builder(dir)
+                .proportional_restore(proportional)
+                .build()
+                .unwrap()
+        };
+
+        let dir = fake_led("255\n", "51\n", "[none]\n");
+        let led = open(&dir, true);
+        fs::write(dir.path().join("max_brightness"), "1000\n").unwrap();
+        drop(led);
+        assert_eq!(read(&dir, "brightness"), "200");
+
+        // without the flag the raw value is restored
+        let dir = fake_led("255\n", "51\n", "[none]\n");
+        let led = open(&dir, false);
+        fs::write(dir.path().join("max_brightness"), "1000\n").unwrap();
+        drop(led);
+        assert_eq!(read(&dir, "brightness"), "51");
+
+        let dir = fake_led("255\n", "255\n", "[none]\n");
+        let led = open(&dir, true);
+        fs::write(dir.path().join("max_brightness"), "1\n").unwrap();
+        drop(led);
+        assert_eq!(read(&dir, "brightness"), "1");
+    }
+
+    #[test]
+    fn throttle() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut throttle = Throttle::new(ms(100));
+
+        assert_eq!(throttle.delay(start), Duration::ZERO);
+        assert_eq!(throttle.delay(start + ms(30)), ms(70));
+        // the previous write was delayed until 100 ms
+        assert_eq!(throttle.delay(start + ms(150)), ms(50));
+        assert_eq!(throttle.delay(start + ms(400)), Duration::ZERO);
+        assert_eq!(throttle.delay(start + ms(500)), Duration::ZERO);
+    }
+
+    #[test]
+    fn no_throttle() {
+        let start = Instant::now();
+        let mut throttle = Throttle::default();
+
+        assert_eq!(throttle.delay(start), Duration::ZERO);
+        assert_eq!(throttle.delay(start), Duration::ZERO);
+    }
+
+    #[test]
+    fn inspect() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(4, 15, 1, &running);
+        let timing = Timing::new(
+            10,
+            Pause::Fixed(Duration::ZERO),
+            DutyCycle(0.2),
+            DutyCycle(0.6),
+        );
+        let mut displayed = Vec::new();
+        let mut led = Inspect::new(crate::tests::MockLed::default(), |symbol, on, off| {
+            displayed.push((symbol, on.as_millis(), off.as_millis()))
+        });
+
+        crate::run(&mut led, &timing, Format::Hour12, &time, &running).unwrap();
+        assert_eq!(led.led.0.len(), 10);
+        drop(led);
+
+        assert_eq!(
+            displayed,
+            [
+                (Symbol::Long, 6, 4),
+                (Symbol::Long, 6, 4),
+                (Symbol::Short, 2, 8),
+                (Symbol::Break(BreakKind::Group), 0, 10),
+                (Symbol::Long, 6, 4),
+                (Symbol::Long, 6, 4),
+            ]
+        );
+    }
+
+    #[cfg(feature = "embedded-hal")]
+    #[test]
+    fn pin_toggle() {
+        use embedded_hal::digital::{ErrorType, OutputPin};
+        use std::convert::Infallible;
+
+        #[derive(Default)]
+        struct MockPin(Vec<bool>);
+
+        impl ErrorType for MockPin {
+            type Error = Infallible;
+        }
+
+        impl OutputPin for MockPin {
+            fn set_low(&mut self) -> Result<(), Self::Error> {
+                self.0.push(false);
+                Ok(())
+            }
+
+            fn set_high(&mut self) -> Result<(), Self::Error> {
+                self.0.push(true);
+                Ok(())
+            }
+        }
+
+        let mut led = PinLed::new(MockPin::default());
+
+        led.on().unwrap();
+        led.off().unwrap();
+        led.display(Symbol::Long, Duration::ZERO, Duration::ZERO)
+            .unwrap();
+        led.display(
+            Symbol::Break(BreakKind::Group),
+            Duration::ZERO,
+            Duration::ZERO,
+        )
+        .unwrap();
+
+        assert_eq!(led.into_inner().0, [true, false, true, false]);
+    }
+
+    #[test]
+    fn sim_output() {
+        let mut led = SimLed::new(Vec::new());
+
+        led.off().unwrap();
+        led.display(Symbol::Short, Duration::ZERO, Duration::ZERO)
+            .unwrap();
+        led.display(
+            Symbol::Break(BreakKind::Group),
+            Duration::ZERO,
+            Duration::ZERO,
+        )
+        .unwrap();
+        led.on().unwrap();
+
+        let output = String::from_utf8(led.into_inner()).unwrap();
+        assert_eq!(output, "\r○\r●\r○\r●");
+    }
+
+    #[test]
+    fn rgb_colors() {
+        assert_eq!("00ff80".parse::<Rgb>().unwrap(), Rgb(0, 0xff, 0x80));
+        assert_eq!("#FF0000".parse::<Rgb>().unwrap(), Rgb(0xff, 0, 0));
+        for color in ["", "fff", "00ff800", "00gg00", "\u{e9}\u{e9}\u{e9}"] {
+            assert!(
+                matches!(color.parse::<Rgb>(), Err(Error::InvalidColor)),
+                "{}",
+                color
+            );
+        }
+    }
+
+    #[test]
+    fn rgb_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let channels = ["red", "green", "blue"].map(|color| dir.path().join(color));
+        for (channel, max_brightness) in channels.iter().zip(["255", "1", "4095"]) {
+            fs::create_dir(channel).unwrap();
+            fs::write(channel.join("max_brightness"), max_brightness).unwrap();
+            fs::write(channel.join("brightness"), "0").unwrap();
+            fs::write(channel.join("trigger"), "[none]").unwrap();
+        }
+        let brightness = || {
+            channels
+                .clone()
+                .map(|channel| fs::read_to_string(channel.join("brightness")).unwrap())
+        };
+
+        let mut led = RgbSysfsLed::new(channels.clone()).unwrap();
+        led.set_color(Symbol::Short, Rgb(0, 0xff, 0x80));
+        led.set_color(Symbol::Long, Rgb(0xff, 0x40, 0));
+
+        // the channels lit while displaying a short and a long
+        let mut written = Vec::new();
+        for symbol in [Symbol::Short, Symbol::Long] {
+            led.current = led.color(symbol);
+            led.on().unwrap();
+            written.push(brightness());
+
+            led.display(symbol, Duration::ZERO, Duration::ZERO).unwrap();
+            assert_eq!(brightness(), ["0", "0", "0"]);
+        }
+
+        assert_eq!(written, [["0", "1", "2056"], ["255", "0", "0"]]);
+    }
+
+    #[test]
+    fn fifo_lines() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 1, &running);
+        let timing = Timing::new(
+            10,
+            Pause::Fixed(Duration::ZERO),
+            DutyCycle(0.2),
+            DutyCycle(0.6),
+        );
+        let mut led = FifoLed::new(Vec::new());
+
+        crate::run(&mut led, &timing, Format::Hour12, &time, &running).unwrap();
+
+        let output = String::from_utf8(led.into_inner()).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+
+        assert_eq!(
+            lines,
+            [
+                "long 6 4",
+                "long 6 4",
+                "short 2 8",
+                "break 0 10",
+                "long 6 4",
+                "long 6 4",
+                "long 6 4",
+                "long 6 4",
+            ]
+        );
+    }
+}
@@ -0,0 +1,1716 @@
+#![forbid(unsafe_code)]
+
+//! # morseclock-bin - Drive a LED with a [`morseclock::Clock`]
+//!
+//! The building blocks of the `morseclock-hw` binary: LED backends, time sources,
+//! the blink timing and the display loop tying them together.
+
+use chrono::{NaiveDateTime, Timelike};
+use morseclock::{BreakKind, Clock, Format, LedState, MorseExt, Symbol, EOT, PREAMBLE};
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::iter;
+use std::num;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+pub mod audio;
+pub mod config;
+pub mod led;
+pub mod notify;
+pub mod parser;
+pub mod stream;
+pub mod time;
+pub mod timing;
+
+pub use config::Config;
+#[cfg(feature = "embedded-hal")]
+pub use led::PinLed;
+pub use led::{
+    AmPmLed, BreakState, FifoLed, Inspect, Led, Rgb, RgbSysfsLed, SimLed, SysfsLed, SysfsLedBuilder,
+};
+pub use notify::Notify;
+#[cfg(feature = "systemd")]
+pub use notify::Systemd;
+use notify::Watchdog;
+pub use time::{FixedTime, LocalTime, NoTime, TimeSource, Zone};
+pub use timing::{DutyCycle, Pause, Ratio, Timing};
+
+/// The pattern displayed instead of a time which hasn't been set, see [`NoTime`]
+///
+/// The longs are displayed slowly, with the LED on and off for the base duration.
+pub const NO_TIME: [Symbol; 3] = [Symbol::Long; 3];
+
+/// The pattern blinked to check the wiring and timing of a LED, SOS (`"...---..."`)
+///
+/// Both kinds of blinks are displayed three times in a row, easy to tell apart by eye.
+pub const SELF_TEST: [Symbol; 9] = [
+    Symbol::Short,
+    Symbol::Short,
+    Symbol::Short,
+    Symbol::Long,
+    Symbol::Long,
+    Symbol::Long,
+    Symbol::Short,
+    Symbol::Short,
+    Symbol::Short,
+];
+
+/// A collection of errors which can happen
+#[derive(Debug)]
+pub enum Error {
+    InvalidBreakState,
+    InvalidColor,
+    InvalidConfig(String),
+    InvalidDuration,
+    InvalidDutyCycle,
+    InvalidFormat,
+    InvalidMessage(String),
+    InvalidNoTime,
+    InvalidRatio,
+    InvalidSpeed,
+    InvalidZone,
+    MissingKey(&'static str),
+    NotWritable(PathBuf),
+    ParseError(num::ParseFloatError),
+    ParseIntError(num::ParseIntError),
+    TooShort(&'static str, Duration),
+    ZeroMaxBrightness(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidBreakState => f.write_str("Invalid break state"),
+            Self::InvalidColor => f.write_str("Invalid color"),
+            Self::InvalidConfig(pair) => write!(f, "Invalid config entry: {}", pair),
+            Self::InvalidDuration => f.write_str("Invalid duration"),
+            Self::InvalidDutyCycle => f.write_str("Invalid duty cycle"),
+            Self::InvalidFormat => f.write_str("Invalid format"),
+            Self::InvalidMessage(line) => write!(f, "Invalid message: {:?}", line.trim()),
+            Self::InvalidNoTime => f.write_str("Invalid no-time behavior"),
+            Self::InvalidRatio => f.write_str("Invalid ratio"),
+            Self::InvalidSpeed => f.write_str("Invalid speed"),
+            Self::InvalidZone => f.write_str("Invalid timezone"),
+            Self::MissingKey(key) => write!(f, "Missing config key: {}", key),
+            Self::NotWritable(path) => write!(f, "{} is not writable", path.display()),
+            Self::ParseError(e) => write!(f, "Parsing failed: {}", e),
+            Self::ParseIntError(e) => write!(f, "Parsing failed: {}", e),
+            Self::TooShort(name, min) => write!(f, "The {} must be at least {:?}", name, min),
+            Self::ZeroMaxBrightness(path) => {
+                write!(f, "{} reports a maximum brightness of 0", path.display())
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<num::ParseFloatError> for Error {
+    fn from(error: num::ParseFloatError) -> Self {
+        Self::ParseError(error)
+    }
+}
+
+impl From<num::ParseIntError> for Error {
+    fn from(error: num::ParseIntError) -> Self {
+        Self::ParseIntError(error)
+    }
+}
+
+/// Repeatedly calls `probe` until it succeeds or `timeout` has passed
+///
+/// After dropping privileges, the LED may briefly not be writable while udev applies
+/// new permissions. Passing a test write as `probe` waits until writing works,
+/// returning the last error of `probe` on timeout.
+pub fn wait_writable<F>(mut probe: F, timeout: Duration) -> anyhow::Result<()>
+where
+    F: FnMut() -> anyhow::Result<()>,
+{
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    let start = Instant::now();
+
+    loop {
+        match probe() {
+            Ok(()) => return Ok(()),
+            Err(e) if start.elapsed() >= timeout => {
+                return Err(e.context(format!("LED not writable after {:?}", timeout)))
+            }
+            Err(_) => thread::sleep(POLL_INTERVAL.min(timeout)),
+        }
+    }
+}
+
+/// The status of a running display loop, shared with [`run_with`] via [`Options`]
+#[derive(Debug, Default)]
+pub struct Status {
+    cycles: AtomicU64,
+}
+
+impl Status {
+    /// The number of cycles which have been displayed completely
+    pub fn cycles(&self) -> u64 {
+        self.cycles.load(Ordering::Relaxed)
+    }
+}
+
+/// Holds the display of [`run_with`] after every cycle until it is advanced
+#[derive(Debug, Default)]
+pub struct Hold {
+    advance: AtomicBool,
+}
+
+impl Hold {
+    /// Starts the next cycle, or skips the next hold if no cycle is being held
+    pub fn advance(&self) {
+        self.advance.store(true, Ordering::Relaxed);
+    }
+
+    /// Waits until the display is advanced, returning `false` if `running` is cleared
+    fn wait(&self, running: &AtomicBool, watchdog: &mut Watchdog) -> anyhow::Result<bool> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+        while !self.advance.swap(false, Ordering::Relaxed) {
+            if !running.load(Ordering::Relaxed) {
+                return Ok(false);
+            }
+
+            watchdog.poll()?;
+            thread::sleep(POLL_INTERVAL.min(watchdog.poll_interval().unwrap_or(POLL_INTERVAL)));
+        }
+
+        Ok(true)
+    }
+}
+
+/// Displays `symbols` on `led`
+///
+/// Returns `false` if the display was interrupted by clearing `running`.
+fn display<L, I>(
+    led: &mut L,
+    timing: &Timing,
+    symbols: I,
+    running: &AtomicBool,
+) -> anyhow::Result<bool>
+where
+    L: Led,
+    I: IntoIterator<Item = Symbol>,
+{
+    for sym in symbols {
+        if !running.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+
+        let (on, off) = timing.durations(sym);
+        log::trace!("{:?}: on {:?}, off {:?}", sym, on, off);
+        led.display(sym, on, off)?;
+    }
+
+    Ok(true)
+}
+
+/// Blinks `symbols` on `led` once
+///
+/// Unlike [`run`], no time is displayed and there is no pause afterwards, which is
+/// useful for testing a LED. Stops early if `running` is cleared.
+pub fn play<L: Led>(
+    led: &mut L,
+    timing: &Timing,
+    symbols: &[Symbol],
+    running: &AtomicBool,
+) -> anyhow::Result<()> {
+    display(led, timing, symbols.iter().copied(), running)?;
+
+    Ok(())
+}
+
+/// Encodes `id` as its decimal digits in international morse code
+///
+/// The digits are separated by a [`BreakKind::Digit`] break, e.g. `42` is encoded as
+/// `....- ..---`.
+pub fn identification(id: u32) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+
+    for (i, digit) in id
+        .to_string()
+        .bytes()
+        .map(|b| usize::from(b - b'0'))
+        .enumerate()
+    {
+        if i > 0 {
+            symbols.push(Symbol::Break(BreakKind::Digit));
+        }
+
+        let (first, count, rest) = match digit {
+            1..=5 => (Symbol::Short, digit, Symbol::Long),
+            0 => (Symbol::Long, 5, Symbol::Short),
+            _ => (Symbol::Long, digit - 5, Symbol::Short),
+        };
+
+        symbols.extend(iter::repeat_n(first, count).chain(iter::repeat_n(rest, 5 - count)));
+    }
+
+    symbols
+}
+
+/// Options controlling what is displayed in every cycle of [`run_with`]
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// The format of the displayed time
+    pub format: Format,
+    /// Display the [`PREAMBLE`] before every time
+    pub preamble: bool,
+    /// Display the [`EOT`] after every time
+    pub eot: bool,
+    /// The status updated after every cycle
+    pub status: Option<Arc<Status>>,
+    /// What to display while the time hasn't been set
+    pub on_no_time: NoTime,
+    /// Display the seconds after the minute
+    pub seconds: bool,
+    /// An [identification](identification) displayed once before the first time
+    pub id: Option<u32>,
+    /// Skip cycles which would take longer than this, switching the LED off instead
+    pub max_cycle_duration: Option<Duration>,
+    /// Instead of pausing, switch the LED off and wait until the hold is advanced
+    pub hold: Option<Arc<Hold>>,
+    /// Return after the first cycle instead of pausing, e.g. to display the time from cron
+    pub once: bool,
+    /// How many times every time is displayed before the pause, 0 behaves like 1
+    pub repeat: u32,
+    /// The gap between two repetitions of a time, see [`Options::repeat`]
+    pub repeat_gap: Duration,
+    /// A file the status of the last cycle is written to as JSON, see [`write_status_file`]
+    pub status_file: Option<PathBuf>,
+    /// Notified at the start of every cycle, and its watchdog pinged while pausing or holding
+    pub notify: Option<Arc<dyn Notify>>,
+}
+
+impl Options {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            preamble: false,
+            eot: false,
+            status: None,
+            on_no_time: NoTime::Display,
+            seconds: false,
+            id: None,
+            max_cycle_duration: None,
+            hold: None,
+            once: false,
+            repeat: 1,
+            repeat_gap: Duration::ZERO,
+            status_file: None,
+            notify: None,
+        }
+    }
+}
+
+/// The symbols of a cycle displaying `now` according to `options`
+pub(crate) fn cycle_symbols(
+    now: NaiveDateTime,
+    options: &Options,
+) -> anyhow::Result<impl Iterator<Item = Symbol> + Clone> {
+    Ok(Cycle::new(now, options)?.symbols())
+}
+
+/// A cycle made up of the clock and the symbols displayed around it
+#[derive(Clone, Copy, Debug)]
+struct Cycle {
+    preamble: &'static [Symbol],
+    clock: Clock,
+    eot: &'static [Symbol],
+}
+
+impl Cycle {
+    /// The cycle displaying `now` according to `options`
+    fn new(now: NaiveDateTime, options: &Options) -> anyhow::Result<Self> {
+        let clock = Clock::from_time(&now, options.format)?;
+        let clock = Clock {
+            second: options
+                .seconds
+                .then(|| now.second().try_into())
+                .transpose()?,
+            ..clock
+        };
+
+        Ok(Self {
+            preamble: if options.preamble { &PREAMBLE } else { &[] },
+            clock,
+            eot: if options.eot { &EOT } else { &[] },
+        })
+    }
+
+    fn symbols(&self) -> impl Iterator<Item = Symbol> + Clone {
+        self.preamble
+            .iter()
+            .copied()
+            .chain(self.clock)
+            .chain(self.eot.iter().copied())
+    }
+
+    /// Splits the cycle for [`run_split`]
+    ///
+    /// Returns the symbols displayed on both LEDs before the time, i.e. the preamble and
+    /// the date, the hour hand, the other hands and the symbols displayed on both LEDs
+    /// afterwards.
+    fn split(&self) -> (Vec<Symbol>, Vec<Symbol>, Vec<Symbol>, Vec<Symbol>) {
+        let time = Clock {
+            date: None,
+            ..self.clock
+        };
+        let date = self.clock.into_iter().count() - time.into_iter().count();
+        let before = self
+            .preamble
+            .iter()
+            .copied()
+            .chain(self.clock.into_iter().take(date))
+            .collect();
+        let (hour, minute) = split_hands(time);
+
+        (before, hour, minute, self.eot.to_vec())
+    }
+}
+
+/// Displays the current time of `time` on `led` once and returns the displayed symbols
+///
+/// Unlike [`run`], there is neither a pause afterwards nor a way to interrupt the cycle.
+pub fn run_once<L: Led, T: TimeSource>(
+    led: &mut L,
+    timing: &Timing,
+    format: Format,
+    time: &T,
+) -> anyhow::Result<Vec<Symbol>> {
+    let now = time.now();
+    let symbols: Vec<_> = cycle_symbols(now, &Options::new(format))?.collect();
+
+    led.start_cycle(now)?;
+    display(led, timing, symbols.iter().copied(), &AtomicBool::new(true))?;
+    led.end_cycle()?;
+
+    Ok(symbols)
+}
+
+/// Writes the LED states displaying the current time of `time` to `writer`
+///
+/// Every line holds the start of a state relative to the start of the cycle and its
+/// duration, see [`morseclock::schedule_with`], followed by the pause. Nothing is
+/// displayed, which helps to check a new `timing` before displaying it on a LED.
+pub fn write_schedule<W: io::Write, T: TimeSource>(
+    mut writer: W,
+    timing: &Timing,
+    options: &Options,
+    time: &T,
+) -> anyhow::Result<()> {
+    let now = time.now();
+    let mut start = Duration::ZERO;
+
+    writeln!(writer, "Schedule of {}", now.format("%H:%M:%S"))?;
+
+    let schedule = morseclock::schedule_with(cycle_symbols(now, options)?, |symbol| {
+        timing.durations(symbol)
+    });
+
+    for (state, duration) in schedule {
+        let state = match state {
+            LedState::On => "on",
+            LedState::Off => "off",
+        };
+
+        writeln!(
+            writer,
+            "{:>8} ms  {:<3} for {:>5} ms",
+            start.as_millis(),
+            state,
+            duration.as_millis(),
+        )?;
+        start += duration;
+    }
+
+    if options.repeat > 1 {
+        writeln!(
+            writer,
+            "{:>8} ms  repeat {} times with a gap of {} ms",
+            start.as_millis(),
+            options.repeat - 1,
+            options.repeat_gap.as_millis()
+        )?;
+        start = start * options.repeat + options.repeat_gap * (options.repeat - 1);
+    }
+
+    match timing.pause {
+        Pause::Fixed(pause) => writeln!(
+            writer,
+            "{:>8} ms  pause for {} ms",
+            start.as_millis(),
+            pause.as_millis()
+        )?,
+        Pause::NextMinute => writeln!(
+            writer,
+            "{:>8} ms  pause until the next minute",
+            start.as_millis()
+        )?,
+        Pause::Aligned(period) => writeln!(
+            writer,
+            "{:>8} ms  pause until the next multiple of {} ms",
+            start.as_millis(),
+            period.as_millis()
+        )?,
+    }
+
+    Ok(())
+}
+
+/// Replaces the file `path` by a JSON object describing a displayed cycle
+///
+/// The object holds the `hour` and `minute` of `now`, the displayed `morse` string, the
+/// `timestamp` of `now` and the number of displayed `cycles`, e.g.
+/// `{"hour":16,"minute":47,"morse":"--.=----","timestamp":"2024-07-04T16:47:00","cycles":1}`.
+/// The object is written to a temporary file next to `path` which is then renamed, so
+/// readers never see a partially written file.
+pub fn write_status_file(
+    path: &Path,
+    now: NaiveDateTime,
+    morse: &str,
+    cycles: u64,
+) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+
+    // the morse glyphs never need to be escaped
+    let json = format!(
+        "{{\"hour\":{},\"minute\":{},\"morse\":\"{}\",\"timestamp\":\"{}\",\"cycles\":{}}}\n",
+        now.hour(),
+        now.minute(),
+        morse,
+        now.format("%Y-%m-%dT%H:%M:%S"),
+        cycles,
+    );
+
+    fs::write(&temp, json)?;
+    fs::rename(&temp, path)
+}
+
+/// Displays the time of `time` on `led` until `running` is cleared
+///
+/// Each cycle blinks the current time once, followed by the pause of `timing`.
+/// `running` is checked before every symbol and during the pause, so the function
+/// returns shortly after it has been cleared.
+pub fn run<L: Led, T: TimeSource>(
+    led: &mut L,
+    timing: &Timing,
+    format: Format,
+    time: &T,
+    running: &AtomicBool,
+) -> anyhow::Result<()> {
+    run_with(led, timing, &Options::new(format), time, running)
+}
+
+/// Like [`run`], but displays every cycle according to `options`
+pub fn run_with<L: Led, T: TimeSource>(
+    led: &mut L,
+    timing: &Timing,
+    options: &Options,
+    time: &T,
+    running: &AtomicBool,
+) -> anyhow::Result<()> {
+    run_cycles(led, timing, options, time, running, |led, cycle| {
+        display(led, timing, cycle.symbols(), running)
+    })
+}
+
+/// The display loop of [`run_with`] and [`run_split`]
+///
+/// Every cycle is displayed by `show`, everything else, e.g. the identification or the
+/// pattern displayed without a plausible time, on `led`.
+fn run_cycles<L, T, F>(
+    led: &mut L,
+    timing: &Timing,
+    options: &Options,
+    time: &T,
+    running: &AtomicBool,
+    mut show: F,
+) -> anyhow::Result<()>
+where
+    L: Led,
+    T: TimeSource,
+    F: FnMut(&mut L, &Cycle) -> anyhow::Result<bool>,
+{
+    if let Some(id) = options.id {
+        // separated from the first time by a longer gap than the one between two hands
+        let symbols = identification(id)
+            .into_iter()
+            .chain(iter::repeat_n(Symbol::Break(BreakKind::Group), 7));
+
+        if !display(led, timing, symbols, running)? {
+            return Ok(());
+        }
+    }
+
+    let mut watchdog = Watchdog::new(options.notify.as_deref());
+    let mut cycles = 0;
+    while running.load(Ordering::Relaxed) {
+        watchdog.ping()?;
+
+        let now = time.now();
+        if !running.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let completed = match options.on_no_time {
+            NoTime::Pattern if !time::is_plausible(&now) => {
+                // on and off for the base duration each
+                let slow = Timing {
+                    base: timing.base.saturating_mul(2),
+                    long_duty: DutyCycle(0.5),
+                    ..*timing
+                };
+
+                display(led, &slow, NO_TIME, running)?
+            }
+            NoTime::Wait if !time::is_plausible(&now) => true,
+            _ => {
+                let cycle = Cycle::new(now, options)?;
+                let symbols: Vec<_> = cycle.symbols().collect();
+                let too_long = options
+                    .max_cycle_duration
+                    .filter(|&max| timing.display_duration(symbols.iter().copied()) > max);
+
+                if let Some(max) = too_long {
+                    log::warn!("Skipping a cycle taking longer than {:?}", max);
+                    led.off()?;
+
+                    true
+                } else {
+                    log::debug!("Displaying {}", now.format("%H:%M:%S"));
+                    led.start_cycle(now)?;
+                    let mut completed = true;
+                    for repeat in 0..options.repeat.max(1) {
+                        completed = (repeat == 0
+                            || sleep(options.repeat_gap, running, &mut watchdog)?)
+                            && show(led, &cycle)?;
+
+                        if !completed {
+                            break;
+                        }
+                    }
+                    led.end_cycle()?;
+
+                    if completed {
+                        cycles += 1;
+
+                        if let Some(status) = &options.status {
+                            status.cycles.fetch_add(1, Ordering::Relaxed);
+                        }
+
+                        if let Some(path) = &options.status_file {
+                            let morse: String = symbols.iter().copied().morse().collect();
+
+                            if let Err(e) = write_status_file(path, now, &morse, cycles) {
+                                log::warn!(
+                                    "Writing the status file {} failed: {}",
+                                    path.display(),
+                                    e
+                                );
+                            }
+                        }
+                    }
+
+                    completed
+                }
+            }
+        };
+
+        if !completed || options.once {
+            break;
+        }
+
+        if let Some(hold) = &options.hold {
+            led.off()?;
+
+            if !hold.wait(running, &mut watchdog)? {
+                break;
+            }
+
+            continue;
+        }
+
+        let pause = match timing.pause {
+            Pause::Fixed(pause) => pause,
+            Pause::NextMinute => time::until_next_minute(now, time.now()),
+            Pause::Aligned(period) => time::until_next_boundary(now, time.now(), period),
+        };
+
+        if !sleep(pause, running, &mut watchdog)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Blinks `symbols` on `led` over and over until `running` is cleared
+///
+/// The repetitions are separated by the pause of `timing`. As no time is displayed,
+/// a [`Pause::NextMinute`] or [`Pause::Aligned`] pauses for the base duration instead.
+/// The watchdog of `notify` is pinged before every repetition and during the pauses.
+pub fn run_pattern<L: Led>(
+    led: &mut L,
+    timing: &Timing,
+    symbols: &[Symbol],
+    running: &AtomicBool,
+    notify: Option<&dyn Notify>,
+) -> anyhow::Result<()> {
+    let pause = match timing.pause {
+        Pause::Fixed(pause) => pause,
+        Pause::NextMinute | Pause::Aligned(_) => timing.base,
+    };
+
+    let mut watchdog = Watchdog::new(notify);
+    while running.load(Ordering::Relaxed) {
+        watchdog.ping()?;
+
+        if !display(led, timing, symbols.iter().copied(), running)?
+            || !sleep(pause, running, &mut watchdog)?
+        {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits the symbols of a clock at its first [`BreakKind::Group`] break
+///
+/// Returns the symbols of the hour hand and the ones of the remaining hands, without
+/// the break in between.
+pub fn split_hands<I: IntoIterator<Item = Symbol>>(symbols: I) -> (Vec<Symbol>, Vec<Symbol>) {
+    let mut symbols = symbols.into_iter();
+    let hour = symbols
+        .by_ref()
+        .take_while(|&symbol| symbol != Symbol::Break(BreakKind::Group))
+        .collect();
+
+    (hour, symbols.collect())
+}
+
+/// Like [`run_with`], but displays the hour hand on `hour_led` and the other hands on
+/// `minute_led` at the same time
+///
+/// Every cycle waits for both LEDs to finish before pausing. Everything but the
+/// time, e.g. the identification, the preamble or the date, is blinked on both LEDs
+/// in lockstep.
+pub fn run_split<A: Led, B: Led + Send, T: TimeSource>(
+    hour_led: &mut A,
+    minute_led: &mut B,
+    timing: &Timing,
+    options: &Options,
+    time: &T,
+    running: &AtomicBool,
+) -> anyhow::Result<()> {
+    let mut leds = SplitLeds {
+        hour: hour_led,
+        minute: minute_led,
+    };
+
+    run_cycles(&mut leds, timing, options, time, running, |leds, cycle| {
+        let (before, hour, minute, after) = cycle.split();
+
+        if !display(leds, timing, before, running)? {
+            return Ok(false);
+        }
+
+        let (hour, minute) = thread::scope(|scope| {
+            let minute = scope.spawn(|| display(leds.minute, timing, minute, running));
+            let hour = display(leds.hour, timing, hour, running);
+
+            (hour, minute.join().expect("the minute LED panicked"))
+        });
+
+        Ok(hour? && minute? && display(leds, timing, after, running)?)
+    })
+}
+
+/// The two LEDs of [`run_split`], switched together
+struct SplitLeds<'a, A, B> {
+    hour: &'a mut A,
+    minute: &'a mut B,
+}
+
+impl<A: Led, B: Led> Led for SplitLeds<'_, A, B> {
+    fn on(&mut self) -> anyhow::Result<()> {
+        self.hour.on()?;
+        self.minute.on()
+    }
+
+    fn off(&mut self) -> anyhow::Result<()> {
+        self.hour.off()?;
+        self.minute.off()
+    }
+
+    fn start_cycle(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
+        self.hour.start_cycle(now)?;
+        self.minute.start_cycle(now)
+    }
+
+    fn end_cycle(&mut self) -> anyhow::Result<()> {
+        self.hour.end_cycle()?;
+        self.minute.end_cycle()
+    }
+}
+
+/// Sleeps for `pause` in chunks, returning `false` as soon as `running` is cleared
+///
+/// The chunks are at most 200 ms long and end at a deadline, so the sleep never ends
+/// early, e.g. just before the minute a [`Pause::NextMinute`] waits for. `watchdog` is
+/// polled between the chunks, which are shortened to its poll interval.
+fn sleep(pause: Duration, running: &AtomicBool, watchdog: &mut Watchdog) -> anyhow::Result<bool> {
+    const CHUNK: Duration = Duration::from_millis(200);
+
+    let chunk = CHUNK.min(watchdog.poll_interval().unwrap_or(CHUNK));
+    let deadline = Instant::now() + pause;
+
+    loop {
+        if !running.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+
+        watchdog.poll()?;
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(true);
+        }
+
+        thread::sleep(chunk.min(deadline - now));
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::notify::tests::MockNotify;
+    use chrono::NaiveDate;
+    use morseclock::{MorseExt, SymbolSequence};
+    use std::cell::Cell;
+    use std::sync::Mutex;
+
+    /// A LED which records every state it was switched to
+    #[derive(Debug, Default)]
+    pub struct MockLed(pub Vec<bool>);
+
+    impl Led for MockLed {
+        fn on(&mut self) -> anyhow::Result<()> {
+            self.0.push(true);
+            Ok(())
+        }
+
+        fn off(&mut self) -> anyhow::Result<()> {
+            self.0.push(false);
+            Ok(())
+        }
+    }
+
+    /// A fixed time source which clears `running` after `cycles` readings
+    pub struct StopAfter<'a> {
+        pub time: NaiveDateTime,
+        pub cycles: u32,
+        pub running: &'a AtomicBool,
+        pub calls: Cell<u32>,
+    }
+
+    impl<'a> StopAfter<'a> {
+        pub fn new(hour: u32, minute: u32, cycles: u32, running: &'a AtomicBool) -> Self {
+            Self {
+                time: NaiveDate::from_ymd_opt(2024, 7, 4)
+                    .unwrap()
+                    .and_hms_opt(hour, minute, 0)
+                    .unwrap(),
+                cycles,
+                running,
+                calls: Cell::new(0),
+            }
+        }
+    }
+
+    impl TimeSource for StopAfter<'_> {
+        fn now(&self) -> NaiveDateTime {
+            self.calls.set(self.calls.get() + 1);
+
+            if self.calls.get() > self.cycles {
+                self.running.store(false, Ordering::Relaxed);
+            }
+
+            self.time
+        }
+    }
+
+    /// A timing which doesn't sleep at all
+    pub const ZERO: Timing = Timing {
+        base: Duration::ZERO,
+        pause: Pause::Fixed(Duration::ZERO),
+        short_duty: DutyCycle(0.2),
+        long_duty: DutyCycle(0.6),
+        collapsed_breaks: None,
+    };
+
+    /// The LED states of a single blink sequence described by a morse string
+    pub fn states(morse: &str) -> Vec<bool> {
+        morse
+            .chars()
+            .filter(|c| *c != '=' && *c != ' ')
+            .flat_map(|_| [true, false])
+            .collect()
+    }
+
+    #[test]
+    fn play_symbols() {
+        let running = AtomicBool::new(true);
+        let mut led = MockLed::default();
+
+        play(
+            &mut led,
+            &ZERO,
+            &[
+                Symbol::Short,
+                Symbol::Break(BreakKind::Group),
+                Symbol::Long,
+                Symbol::Long,
+            ],
+            &running,
+        )
+        .unwrap();
+
+        assert_eq!(led.0, states(".=--"));
+
+        running.store(false, Ordering::Relaxed);
+        led.0.clear();
+        play(&mut led, &ZERO, &PREAMBLE, &running).unwrap();
+        assert!(led.0.is_empty());
+    }
+
+    #[test]
+    fn run_single() {
+        let time = FixedTime(
+            NaiveDate::from_ymd_opt(2024, 7, 4)
+                .unwrap()
+                .and_hms_opt(16, 47, 0)
+                .unwrap(),
+        );
+        let mut led = MockLed::default();
+
+        let symbols = run_once(&mut led, &ZERO, Format::Hour24, &time).unwrap();
+        let morse: String = symbols.into_iter().morse().collect();
+
+        assert_eq!(morse, "------.=----");
+        assert_eq!(led.0, states(&morse));
+    }
+
+    #[test]
+    fn schedule() {
+        let time = FixedTime(
+            NaiveDate::from_ymd_opt(2024, 7, 4)
+                .unwrap()
+                .and_hms_opt(16, 47, 0)
+                .unwrap(),
+        );
+        let timing = Timing::new(
+            100,
+            Pause::Fixed(Duration::from_secs(2)),
+            DutyCycle(0.2),
+            DutyCycle(0.6),
+        );
+        let mut schedule = Vec::new();
+
+        write_schedule(&mut schedule, &timing, &Options::new(Format::Hour12), &time).unwrap();
+
+        assert_eq!(
+            String::from_utf8(schedule).unwrap(),
+            "Schedule of 16:47:00\n\
+             \x20      0 ms  on  for    60 ms\n\
+             \x20     60 ms  off for    40 ms\n\
+             \x20    100 ms  on  for    60 ms\n\
+             \x20    160 ms  off for    40 ms\n\
+             \x20    200 ms  on  for    20 ms\n\
+             \x20    220 ms  off for   180 ms\n\
+             \x20    400 ms  on  for    60 ms\n\
+             \x20    460 ms  off for    40 ms\n\
+             \x20    500 ms  on  for    60 ms\n\
+             \x20    560 ms  off for    40 ms\n\
+             \x20    600 ms  on  for    60 ms\n\
+             \x20    660 ms  off for    40 ms\n\
+             \x20    700 ms  on  for    60 ms\n\
+             \x20    760 ms  off for    40 ms\n\
+             \x20    800 ms  pause for 2000 ms\n"
+        );
+
+        let timing = Timing {
+            pause: Pause::NextMinute,
+            ..timing
+        };
+        let mut schedule = Vec::new();
+        write_schedule(&mut schedule, &timing, &Options::new(Format::Hour12), &time).unwrap();
+        assert!(String::from_utf8(schedule)
+            .unwrap()
+            .ends_with("     800 ms  pause until the next minute\n"));
+
+        let options = Options {
+            repeat: 3,
+            repeat_gap: Duration::from_millis(300),
+            ..Options::new(Format::Hour12)
+        };
+        let mut schedule = Vec::new();
+        write_schedule(&mut schedule, &timing, &options, &time).unwrap();
+        assert!(String::from_utf8(schedule).unwrap().ends_with(
+            "     800 ms  repeat 2 times with a gap of 300 ms\n    \
+             3000 ms  pause until the next minute\n"
+        ));
+    }
+
+    /// A LED which records the durations it is asked to display, like a schedule
+    #[derive(Debug, Default)]
+    struct ScheduleLed(Vec<(LedState, Duration)>);
+
+    impl Led for ScheduleLed {
+        fn on(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn off(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn display(&mut self, _: Symbol, on: Duration, off: Duration) -> anyhow::Result<()> {
+            for (state, duration) in [(LedState::On, on), (LedState::Off, off)] {
+                match self.0.last_mut() {
+                    _ if duration.is_zero() => {}
+                    Some((last, total)) if *last == state => *total += duration,
+                    _ => self.0.push((state, duration)),
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn schedule_agrees() {
+        let time = FixedTime(
+            NaiveDate::from_ymd_opt(2024, 7, 4)
+                .unwrap()
+                .and_hms_opt(16, 47, 0)
+                .unwrap(),
+        );
+        let timing = Timing::new(700, Pause::NextMinute, DutyCycle(0.2), DutyCycle(0.6));
+        let clock = Clock::new(
+            16.try_into().unwrap(),
+            47.try_into().unwrap(),
+            Format::Hour12,
+        );
+        let expected = morseclock::schedule(clock, &timing.params());
+
+        // the durations of the display loop
+        let mut led = ScheduleLed::default();
+        run_once(&mut led, &timing, Format::Hour12, &time).unwrap();
+        assert_eq!(led.0, expected);
+
+        // and the ones of the dry run
+        let mut schedule = Vec::new();
+        write_schedule(&mut schedule, &timing, &Options::new(Format::Hour12), &time).unwrap();
+        let schedule = String::from_utf8(schedule).unwrap();
+        let written: Vec<_> = schedule
+            .lines()
+            .filter_map(
+                |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    [_, "ms", state, "for", duration, "ms"] => {
+                        Some((state, duration.parse().unwrap()))
+                    }
+                    _ => None,
+                },
+            )
+            .collect();
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(state, duration)| {
+                let state = if state == LedState::On { "on" } else { "off" };
+                (state, duration.as_millis())
+            })
+            .collect();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn run_cycles() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 2, &running);
+        let mut led = MockLed::default();
+
+        run(&mut led, &ZERO, Format::Hour12, &time, &running).unwrap();
+
+        assert_eq!(led.0, states("--.=------.=----"));
+        assert!(!running.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn run_single_cycle() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 5, &running);
+        let mut led = MockLed::default();
+        let timing = Timing {
+            pause: Pause::Fixed(Duration::from_secs(3600)),
+            ..ZERO
+        };
+        let options = Options {
+            once: true,
+            ..Options::new(Format::Hour12)
+        };
+
+        // returns without the pause
+        run_with(&mut led, &timing, &options, &time, &running).unwrap();
+
+        assert_eq!(led.0, states("--.=----"));
+        assert_eq!(time.calls.get(), 1);
+        assert!(running.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn run_next_minute() {
+        /// A time source following the real time from `start`, stopping at the 4th reading
+        struct RealTime<'a> {
+            start: (NaiveDateTime, Instant),
+            running: &'a AtomicBool,
+            calls: Cell<u32>,
+        }
+
+        impl TimeSource for RealTime<'_> {
+            fn now(&self) -> NaiveDateTime {
+                self.calls.set(self.calls.get() + 1);
+                if self.calls.get() == 4 {
+                    self.running.store(false, Ordering::Relaxed);
+                }
+
+                let (time, instant) = self.start;
+                time + chrono::Duration::from_std(instant.elapsed()).unwrap()
+            }
+        }
+
+        /// A LED which records the displayed times
+        #[derive(Default)]
+        struct CycleLed(Vec<NaiveDateTime>);
+
+        impl Led for CycleLed {
+            fn on(&mut self) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            fn off(&mut self) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            fn start_cycle(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
+                self.0.push(now);
+                Ok(())
+            }
+        }
+
+        let running = AtomicBool::new(true);
+        let date = NaiveDate::from_ymd_opt(2024, 7, 4).unwrap();
+        // 799.5 ms before the next minute, which equal chunks of whole milliseconds miss
+        let time = RealTime {
+            start: (
+                date.and_hms_micro_opt(12, 0, 59, 200_500).unwrap(),
+                Instant::now(),
+            ),
+            running: &running,
+            calls: Cell::new(0),
+        };
+        let mut led = CycleLed::default();
+        let timing = Timing {
+            pause: Pause::NextMinute,
+            ..ZERO
+        };
+
+        run_with(
+            &mut led,
+            &timing,
+            &Options::new(Format::Hour12),
+            &time,
+            &running,
+        )
+        .unwrap();
+
+        // the second time is displayed once the minute has changed, not just before
+        assert_eq!(led.0.len(), 2);
+        assert_eq!(led.0[0].minute(), 0);
+        assert!(
+            led.0[1] >= date.and_hms_opt(12, 1, 0).unwrap(),
+            "{}",
+            led.0[1]
+        );
+        assert!(
+            led.0[1] < date.and_hms_opt(12, 1, 1).unwrap(),
+            "{}",
+            led.0[1]
+        );
+    }
+
+    #[test]
+    fn run_repeat() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 5, &running);
+        let mut led = MockLed::default();
+        let status = Arc::new(Status::default());
+        let options = Options {
+            once: true,
+            repeat: 3,
+            repeat_gap: Duration::from_millis(1),
+            status: Some(status.clone()),
+            ..Options::new(Format::Hour12)
+        };
+
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+
+        // one readout of the same time
+        assert_eq!(led.0, states("--.=----").repeat(3));
+        assert_eq!(time.calls.get(), 1);
+        assert_eq!(status.cycles.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn run_status_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 2, &running);
+        let mut led = MockLed::default();
+        let options = Options {
+            status_file: Some(path.clone()),
+            ..Options::new(Format::Hour12)
+        };
+
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\"hour\":16,\"minute\":47,\"morse\":\"--.=----\",\
+             \"timestamp\":\"2024-07-04T16:47:00\",\"cycles\":2}\n"
+        );
+        // the temporary file has been renamed
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn run_repeat_stopped() {
+        /// Clears `running` on the last blink of "--.=----"
+        struct StopLed<'a>(MockLed, &'a AtomicBool);
+
+        impl Led for StopLed<'_> {
+            fn on(&mut self) -> anyhow::Result<()> {
+                self.0.on()?;
+                if self.0 .0.iter().filter(|&&on| on).count() == 7 {
+                    self.1.store(false, Ordering::Relaxed);
+                }
+                Ok(())
+            }
+
+            fn off(&mut self) -> anyhow::Result<()> {
+                self.0.off()
+            }
+        }
+
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 5, &running);
+        let mut led = StopLed(MockLed::default(), &running);
+        let options = Options {
+            repeat: 3,
+            repeat_gap: Duration::from_secs(3600),
+            ..Options::new(Format::Hour12)
+        };
+        let start = std::time::Instant::now();
+
+        // stops during the first gap instead of waiting for it
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+
+        assert_eq!(led.0 .0, states("--.=----"));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn run_notify() {
+        #[derive(Debug, Default)]
+        struct MockNotify(Arc<Mutex<Vec<&'static str>>>);
+
+        impl Notify for MockNotify {
+            fn ready(&self) -> anyhow::Result<()> {
+                self.0.lock().unwrap().push("ready");
+                Ok(())
+            }
+
+            fn watchdog(&self) -> anyhow::Result<()> {
+                self.0.lock().unwrap().push("watchdog");
+                Ok(())
+            }
+        }
+
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(4, 45, 2, &running);
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut led = Inspect::new(MockLed::default(), |_, _, _| {
+            calls.lock().unwrap().push("symbol")
+        });
+        let options = Options {
+            notify: Some(Arc::new(MockNotify(calls.clone()))),
+            ..Options::new(Format::Hour12)
+        };
+
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+
+        // pinged before every cycle, "--.=----"
+        let cycle = iter::once("watchdog").chain(iter::repeat_n("symbol", 8));
+        let expected: Vec<_> = cycle.clone().chain(cycle).chain(["watchdog"]).collect();
+        assert_eq!(*calls.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn run_preamble() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 2, &running);
+        let mut led = MockLed::default();
+        let options = Options {
+            preamble: true,
+            ..Options::new(Format::Hour12)
+        };
+
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+
+        assert_eq!(led.0, states("-.-.-=--.=-----.-.-=--.=----"));
+    }
+
+    #[test]
+    fn run_eot() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 2, &running);
+        let mut led = MockLed::default();
+        let options = Options {
+            eot: true,
+            ..Options::new(Format::Hour12)
+        };
+
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+
+        assert_eq!(led.0, states("--.=----=...-.---.=----=...-.-"));
+    }
+
+    #[test]
+    fn run_id() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 2, &running);
+        let mut led = MockLed::default();
+        let options = Options {
+            id: Some(42),
+            ..Options::new(Format::Hour12)
+        };
+
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+
+        assert_eq!(led.0, states("....- ..---=--.=------.=----"));
+    }
+
+    #[test]
+    fn id_digits() {
+        let morse = |id| -> String { identification(id).into_iter().morse().collect() };
+
+        assert_eq!(morse(0), "-----");
+        assert_eq!(morse(7), "--...");
+        assert_eq!(morse(1905), ".---- ----. ----- .....");
+    }
+
+    #[test]
+    fn run_max_cycle_duration() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 2, &running);
+        let mut led = MockLed::default();
+        let timing = Timing {
+            base: Duration::from_secs(3600),
+            ..ZERO
+        };
+        let options = Options {
+            max_cycle_duration: Some(Duration::from_secs(60)),
+            ..Options::new(Format::Hour12)
+        };
+
+        // the break between the hands alone takes an hour
+        run_with(&mut led, &timing, &options, &time, &running).unwrap();
+        assert_eq!(led.0, [false, false]);
+
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 1, &running);
+        led.0.clear();
+
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+        assert_eq!(led.0, states("--.=----"));
+    }
+
+    #[test]
+    fn watchdog_while_waiting() {
+        let notify = MockNotify::new(Duration::from_millis(2));
+        let running = AtomicBool::new(true);
+        let hold = Hold::default();
+
+        // both only return once the watchdog has been pinged again and again
+        thread::scope(|s| {
+            s.spawn(|| {
+                while notify.pings() < 3 {
+                    thread::yield_now();
+                }
+                hold.advance();
+            });
+
+            let mut watchdog = Watchdog::new(Some(&notify));
+            assert!(hold.wait(&running, &mut watchdog).unwrap());
+        });
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                while notify.pings() < 6 {
+                    thread::yield_now();
+                }
+                running.store(false, Ordering::Relaxed);
+            });
+
+            let mut watchdog = Watchdog::new(Some(&notify));
+            assert!(!sleep(Duration::from_secs(3600), &running, &mut watchdog).unwrap());
+        });
+    }
+
+    #[test]
+    fn run_pattern_loop() {
+        let running = AtomicBool::new(true);
+        let pattern: SymbolSequence = ".-..-".parse().unwrap();
+        let mut blinks = 0;
+        // stop within the third repetition
+        let mut led = Inspect::new(MockLed::default(), |_, _, _| {
+            blinks += 1;
+            if blinks == 13 {
+                running.store(false, Ordering::Relaxed);
+            }
+        });
+
+        let notify = MockNotify::new(Duration::from_secs(3600));
+
+        run_pattern(&mut led, &ZERO, &pattern.0, &running, Some(&notify)).unwrap();
+
+        assert_eq!(led.led.0, states(".-..-.-..-.-."));
+        // pinged before every repetition
+        assert_eq!(notify.pings(), 3);
+        assert!(".-x".parse::<SymbolSequence>().is_err());
+    }
+
+    #[test]
+    fn split() {
+        let clock = Clock::new(
+            9.try_into().unwrap(),
+            27.try_into().unwrap(),
+            Format::Hour12,
+        );
+        let (hour, minute) = split_hands(clock);
+
+        assert_eq!(hour.iter().copied().morse().collect::<String>(), "----");
+        assert_eq!(minute.iter().copied().morse().collect::<String>(), "--..");
+
+        let (hour, minute) = split_hands([Symbol::Short]);
+        assert_eq!((hour, minute), (vec![Symbol::Short], vec![]));
+    }
+
+    #[test]
+    fn run_split_leds() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(9, 27, 2, &running);
+        let mut hour_led = MockLed::default();
+        let mut minute_led = MockLed::default();
+
+        run_split(
+            &mut hour_led,
+            &mut minute_led,
+            &ZERO,
+            &Options::new(Format::Hour12),
+            &time,
+            &running,
+        )
+        .unwrap();
+
+        assert_eq!(hour_led.0, states("--------"));
+        assert_eq!(minute_led.0, states("--..--.."));
+    }
+
+    #[test]
+    fn run_split_leds_options() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(9, 27, 2, &running);
+        let mut hour_led = MockLed::default();
+        let mut minute_led = MockLed::default();
+        let options = Options {
+            once: true,
+            repeat: 2,
+            ..Options::new(Format::Hour12)
+        };
+
+        run_split(
+            &mut hour_led,
+            &mut minute_led,
+            &ZERO,
+            &options,
+            &time,
+            &running,
+        )
+        .unwrap();
+
+        // displayed twice, but only a single cycle
+        assert_eq!(hour_led.0, states("--------"));
+        assert_eq!(minute_led.0, states("--..--.."));
+        assert!(running.load(Ordering::Relaxed));
+
+        let time = StopAfter::new(9, 27, 2, &running);
+        let mut hour_led = MockLed::default();
+        let mut minute_led = MockLed::default();
+        let options = Options {
+            preamble: true,
+            eot: true,
+            once: true,
+            ..Options::new(Format::Hour12)
+        };
+
+        run_split(
+            &mut hour_led,
+            &mut minute_led,
+            &ZERO,
+            &options,
+            &time,
+            &running,
+        )
+        .unwrap();
+
+        // the preamble and the EOT on both LEDs, each hand only on its own
+        assert_eq!(hour_led.0, states("-.-.-=----=...-.-"));
+        assert_eq!(minute_led.0, states("-.-.-=--..=...-.-"));
+    }
+
+    #[test]
+    fn split_cycle() {
+        let cycle = Cycle {
+            preamble: &PREAMBLE,
+            clock: Clock::with_date(
+                7.try_into().unwrap(),
+                4.try_into().unwrap(),
+                9.try_into().unwrap(),
+                27.try_into().unwrap(),
+                Format::Hour12,
+            ),
+            eot: &EOT,
+        };
+        let morse = |symbols: Vec<Symbol>| symbols.into_iter().morse().collect::<String>();
+        let (before, hour, minute, after) = cycle.split();
+
+        assert_eq!(morse(before), "-.-.-=---.=--.=");
+        assert_eq!(morse(hour), "----");
+        assert_eq!(morse(minute), "--..");
+        assert_eq!(morse(after), "=...-.-");
+        assert_eq!(
+            morse(cycle.symbols().collect()),
+            "-.-.-=---.=--.=----=--..=...-.-"
+        );
+    }
+
+    #[test]
+    fn run_hold() {
+        let running = AtomicBool::new(true);
+        let time = FixedTime(
+            NaiveDate::from_ymd_opt(2024, 7, 4)
+                .unwrap()
+                .and_hms_opt(16, 47, 0)
+                .unwrap(),
+        );
+        let status = Arc::new(Status::default());
+        let hold = Arc::new(Hold::default());
+        let options = Options {
+            status: Some(status.clone()),
+            hold: Some(hold.clone()),
+            ..Options::new(Format::Hour12)
+        };
+        // waits until `cycles` cycles have been displayed
+        let wait_for = |cycles| {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while status.cycles() < cycles {
+                assert!(
+                    Instant::now() < deadline,
+                    "cycle {} wasn't displayed",
+                    cycles
+                );
+                thread::yield_now();
+            }
+        };
+
+        let led = thread::scope(|s| {
+            let display = s.spawn(|| {
+                let mut led = MockLed::default();
+                run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+                led
+            });
+
+            wait_for(1);
+            hold.advance();
+            wait_for(2);
+
+            running.store(false, Ordering::Relaxed);
+            display.join().unwrap()
+        });
+
+        // the display waits for the hold instead of starting a third cycle, and the
+        // LED is switched off while holding
+        assert_eq!(status.cycles(), 2);
+        let mut expected = states("--.=----");
+        expected.push(false);
+        assert_eq!(led.0, [expected.clone(), expected].concat());
+    }
+
+    #[test]
+    fn run_status() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(16, 47, 3, &running);
+        let mut led = MockLed::default();
+        let status = Arc::new(Status::default());
+        let options = Options {
+            status: Some(status.clone()),
+            ..Options::new(Format::Hour12)
+        };
+
+        assert_eq!(status.cycles(), 0);
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+        assert_eq!(status.cycles(), 3);
+    }
+
+    #[test]
+    fn writable_after_delay() {
+        let mut attempts = 0;
+        let probe = || {
+            attempts += 1;
+            if attempts < 3 {
+                anyhow::bail!("Permission denied")
+            }
+            Ok(())
+        };
+
+        wait_writable(probe, Duration::from_secs(5)).unwrap();
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn writable_timeout() {
+        let start = Instant::now();
+        let result = wait_writable(
+            || anyhow::bail!("Permission denied"),
+            Duration::from_millis(20),
+        );
+
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(
+            format!("{:#}", result.unwrap_err()),
+            "LED not writable after 20ms: Permission denied"
+        );
+    }
+
+    #[test]
+    fn run_ampm() {
+        for (hour, marker) in [(9, [false, false]), (21, [true, false])] {
+            let running = AtomicBool::new(true);
+            let time = StopAfter::new(hour, 41, 1, &running);
+            let mut led = AmPmLed::new(MockLed::default(), MockLed::default());
+
+            run(&mut led, &ZERO, Format::Hour12, &time, &running).unwrap();
+
+            assert_eq!(led.led.0, states("----=---.."));
+            assert_eq!(led.marker.0, marker);
+        }
+    }
+
+    #[test]
+    fn run_no_time() {
+        for (on_no_time, expected) in [
+            (NoTime::Display, states("-=-")),
+            (NoTime::Pattern, states("---")),
+            (NoTime::Wait, vec![]),
+        ] {
+            let running = AtomicBool::new(true);
+            let time = StopAfter {
+                time: NaiveDateTime::default(),
+                ..StopAfter::new(0, 0, 1, &running)
+            };
+            let mut led = MockLed::default();
+            let options = Options {
+                on_no_time,
+                ..Options::new(Format::Hour12)
+            };
+
+            run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+            assert_eq!(led.0, expected);
+        }
+    }
+
+    #[test]
+    fn run_seconds() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter {
+            time: NaiveDate::from_ymd_opt(2024, 7, 4)
+                .unwrap()
+                .and_hms_opt(9, 41, 7)
+                .unwrap(),
+            ..StopAfter::new(0, 0, 1, &running)
+        };
+        let mut led = MockLed::default();
+        let options = Options {
+            seconds: true,
+            ..Options::new(Format::Hour12)
+        };
+
+        run_with(&mut led, &ZERO, &options, &time, &running).unwrap();
+        assert_eq!(led.0, states("----=---..=-."));
+    }
+
+    #[test]
+    fn zero_differs_from_break() {
+        let running = AtomicBool::new(true);
+        let time = StopAfter::new(0, 0, 1, &running);
+        let mut led = MockLed::default();
+
+        run(&mut led, &ZERO, Format::Hour12, &time, &running).unwrap();
+
+        // both zero hands light the LED once, the break between them doesn't
+        assert_eq!(led.0, [true, false, true, false]);
+    }
+
+    #[test]
+    fn run_stopped() {
+        let running = AtomicBool::new(false);
+        let mut led = MockLed::default();
+
+        run(
+            &mut led,
+            &ZERO,
+            Format::Hour12,
+            &FixedTime(Default::default()),
+            &running,
+        )
+        .unwrap();
+
+        assert!(led.0.is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+//! Notifying a service manager about the state of the display loop
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A receiver of the state of the display loop, shared with [`run_with`](crate::run_with)
+/// via [`Options`](crate::Options)
+pub trait Notify: fmt::Debug + Send + Sync {
+    /// Called once the LED has been opened and is ready to display the time
+    fn ready(&self) -> anyhow::Result<()>;
+
+    /// Called at the start of every cycle of [`run_with`](crate::run_with) and while
+    /// waiting, showing that the loop isn't hung
+    fn watchdog(&self) -> anyhow::Result<()>;
+
+    /// The interval in which the watchdog has to be pinged, `None` if it only needs to be
+    /// pinged once per cycle
+    fn watchdog_interval(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Pings the watchdog of a [`Notify`] no slower than every half of its
+/// [interval](Notify::watchdog_interval) while waiting
+#[derive(Debug)]
+pub struct Watchdog<'a> {
+    notify: Option<&'a dyn Notify>,
+    last: Option<Instant>,
+}
+
+impl<'a> Watchdog<'a> {
+    pub fn new(notify: Option<&'a dyn Notify>) -> Self {
+        Self { notify, last: None }
+    }
+
+    /// Pings the watchdog right away
+    pub fn ping(&mut self) -> anyhow::Result<()> {
+        if let Some(notify) = self.notify {
+            notify.watchdog()?;
+            self.last = Some(Instant::now());
+        }
+
+        Ok(())
+    }
+
+    /// Pings the watchdog unless it has been pinged within the [poll interval](Watchdog::poll_interval)
+    pub fn poll(&mut self) -> anyhow::Result<()> {
+        match (self.poll_interval(), self.last) {
+            (None, _) => Ok(()),
+            (Some(interval), Some(last)) if last.elapsed() < interval => Ok(()),
+            (Some(_), _) => self.ping(),
+        }
+    }
+
+    /// Half of the watchdog interval, the longest a wait may go without [`Watchdog::poll`]
+    pub fn poll_interval(&self) -> Option<Duration> {
+        Some(self.notify?.watchdog_interval()? / 2)
+    }
+}
+
+/// Notifies systemd about the readiness and pings its watchdog, for a `Type=notify` service
+///
+/// Nothing is sent unless the process has been started by systemd. The watchdog is only
+/// pinged if `WatchdogSec=` is set for the service, which must be longer than the
+/// longest cycle; pauses ping it every half of the interval.
+#[cfg(feature = "systemd")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Systemd {
+    watchdog: Option<Duration>,
+}
+
+#[cfg(feature = "systemd")]
+impl Systemd {
+    /// Reads whether the watchdog is enabled from the environment set up by systemd
+    pub fn from_env() -> Self {
+        Self {
+            watchdog: sd_notify::watchdog_enabled(),
+        }
+    }
+}
+
+#[cfg(feature = "systemd")]
+impl Notify for Systemd {
+    fn ready(&self) -> anyhow::Result<()> {
+        Ok(sd_notify::notify(&[sd_notify::NotifyState::Ready])?)
+    }
+
+    fn watchdog(&self) -> anyhow::Result<()> {
+        if self.watchdog.is_some() {
+            sd_notify::notify(&[sd_notify::NotifyState::Watchdog])?;
+        }
+
+        Ok(())
+    }
+
+    fn watchdog_interval(&self) -> Option<Duration> {
+        self.watchdog
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Counts the pings of the watchdog
+    #[derive(Debug, Default)]
+    pub struct MockNotify {
+        pub interval: Option<Duration>,
+        pub pings: AtomicU32,
+    }
+
+    impl MockNotify {
+        pub fn new(interval: Duration) -> Self {
+            Self {
+                interval: Some(interval),
+                ..Self::default()
+            }
+        }
+
+        pub fn pings(&self) -> u32 {
+            self.pings.load(Ordering::Relaxed)
+        }
+    }
+
+    impl Notify for MockNotify {
+        fn ready(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn watchdog(&self) -> anyhow::Result<()> {
+            self.pings.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn watchdog_interval(&self) -> Option<Duration> {
+            self.interval
+        }
+    }
+
+    #[test]
+    fn watchdog_poll() {
+        let notify = MockNotify::new(Duration::from_secs(3600));
+        let mut watchdog = Watchdog::new(Some(&notify));
+        assert_eq!(watchdog.poll_interval(), Some(Duration::from_secs(1800)));
+
+        // the first poll pings, the next ones wait for half of the interval
+        watchdog.poll().unwrap();
+        watchdog.poll().unwrap();
+        assert_eq!(notify.pings(), 1);
+        watchdog.ping().unwrap();
+        assert_eq!(notify.pings(), 2);
+
+        // polling without an interval never pings
+        let notify = MockNotify::default();
+        let mut watchdog = Watchdog::new(Some(&notify));
+        watchdog.poll().unwrap();
+        assert_eq!(watchdog.poll_interval(), None);
+        assert_eq!(notify.pings(), 0);
+        Watchdog::new(None).poll().unwrap();
+    }
+
+    #[cfg(feature = "systemd")]
+    #[test]
+    fn systemd_messages() {
+        use std::env;
+        use std::os::unix::net::UnixDatagram;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify");
+        let socket = UnixDatagram::bind(&path).unwrap();
+        env::set_var("NOTIFY_SOCKET", &path);
+
+        let mut buf = [0; 64];
+        let mut receive = || {
+            let len = socket.recv(&mut buf).unwrap();
+            String::from_utf8(buf[..len].to_vec()).unwrap()
+        };
+
+        let systemd = Systemd {
+            watchdog: Some(Duration::from_secs(90)),
+        };
+        systemd.ready().unwrap();
+        assert_eq!(receive(), "READY=1\n");
+        systemd.watchdog().unwrap();
+        assert_eq!(receive(), "WATCHDOG=1\n");
+
+        // without a watchdog nothing is sent
+        Systemd { watchdog: None }.watchdog().unwrap();
+        socket.set_nonblocking(true).unwrap();
+        assert!(socket.recv(&mut buf).is_err());
+
+        env::remove_var("NOTIFY_SOCKET");
+    }
+}
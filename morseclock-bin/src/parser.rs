@@ -0,0 +1,145 @@
+//! Parsers for the contents of sysfs LED attribute files
+
+use nom::bytes::complete::tag;
+use nom::error::{ErrorKind, ParseError};
+use nom::sequence::delimited;
+use nom::{AsChar, Finish, IResult, InputTakeAtPosition};
+
+fn trigger_char1<T, E: ParseError<T>>(input: T) -> IResult<T, T, E>
+where
+    T: InputTakeAtPosition,
+    <T as InputTakeAtPosition>::Item: nom::AsChar,
+{
+    input.split_at_position1_complete(
+        |item| !matches!(item.as_char(), '0'..='9' | 'a'..='z' | 'A'..='Z' | '-'),
+        ErrorKind::AlphaNumeric,
+    )
+}
+
+/// Parses the active trigger out of the contents of a sysfs `trigger` file
+///
+/// Returns `None` if no trigger is active (`[none]`) or none could be found
+pub fn parse_trigger(input: &str) -> Option<&str> {
+    let pos = input.find('[')?;
+    let input = &input[pos..];
+
+    let trigger: Result<_, ()> = delimited(tag("["), trigger_char1, tag("]"))(input)
+        .finish()
+        .map(|(_, trigger)| match trigger {
+            "none" => None,
+            trigger => Some(trigger),
+        });
+
+    trigger.unwrap_or(None)
+}
+
+/// Parses all available triggers and the active one out of the contents of a sysfs
+/// `trigger` file
+///
+/// The active trigger is the one returned by [`parse_trigger`].
+pub fn parse_triggers(input: &str) -> (Vec<&str>, Option<&str>) {
+    let triggers = input
+        .split_whitespace()
+        .map(|trigger| {
+            trigger
+                .strip_prefix('[')
+                .and_then(|t| t.strip_suffix(']'))
+                .unwrap_or(trigger)
+        })
+        .filter(|trigger| !trigger.is_empty())
+        .collect();
+
+    (triggers, parse_trigger(input))
+}
+
+/// An entry point for fuzzing the parsers with arbitrary bytes
+///
+/// Meant to be called from a `cargo fuzz` target, e.g.
+/// `fuzz_target!(|data: &[u8]| morseclock_bin::parser::fuzz(data));`
+pub fn fuzz(data: &[u8]) {
+    let input = String::from_utf8_lossy(data);
+    let _ = parse_trigger(&input);
+    let _ = parse_triggers(&input);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger() {
+        assert_eq!(parse_trigger("[none]"), None);
+        assert_eq!(parse_trigger("[usb-gadget]"), Some("usb-gadget"));
+        assert_eq!(parse_trigger("[cpu3]"), Some("cpu3"));
+    }
+
+    #[test]
+    fn find_trigger() {
+        assert_eq!(parse_trigger("some other"), None);
+        assert_eq!(parse_trigger("some other [none]"), None);
+        assert_eq!(
+            parse_trigger("some [processor-14x] banana"),
+            Some("processor-14x")
+        );
+    }
+
+    #[test]
+    fn triggers() {
+        assert_eq!(
+            parse_triggers("none mmc0 [timer] heartbeat\n"),
+            (vec!["none", "mmc0", "timer", "heartbeat"], Some("timer"))
+        );
+        assert_eq!(
+            parse_triggers("[none] usb-gadget"),
+            (vec!["none", "usb-gadget"], None)
+        );
+        assert_eq!(
+            parse_triggers("none timer heartbeat"),
+            (vec!["none", "timer", "heartbeat"], None)
+        );
+        assert_eq!(parse_triggers(""), (vec![], None));
+    }
+
+    #[test]
+    fn adversarial() {
+        for input in [
+            "",
+            "[",
+            "]",
+            "[]",
+            "][",
+            "[[",
+            "]]",
+            "[[none]]",
+            "[timer",
+            "timer]",
+            "[\0]",
+            "[\n]",
+            "\x1b[0m",
+            "[tim\u{e9}r]",
+            "\u{1F4A1}[\u{1F4A1}]",
+        ] {
+            let _ = parse_trigger(input);
+            fuzz(input.as_bytes());
+        }
+
+        assert_eq!(parse_trigger("["), None);
+        assert_eq!(parse_trigger("]"), None);
+        assert_eq!(parse_trigger("[[none]]"), None);
+        assert_eq!(parse_trigger("!! [timer] !!"), Some("timer"));
+
+        fuzz(&[0xff, b'[', 0xfe, b']']);
+    }
+
+    #[test]
+    fn large_input() {
+        let brackets = "[".repeat(1 << 20);
+        assert_eq!(parse_trigger(&brackets), None);
+
+        let trigger = format!("[{}]", "a".repeat(1 << 20));
+        assert_eq!(parse_trigger(&trigger).map(str::len), Some(1 << 20));
+
+        let unbalanced = format!("[{}", "a".repeat(1 << 20));
+        assert_eq!(parse_trigger(&unbalanced), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! Displaying symbols received from another process, see [`run_stream`]
+
+use crate::notify::Watchdog;
+use crate::{display, Error, Led, Notify, Timing};
+use morseclock::{BreakKind, Clock, Format, Symbol};
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Parses a single line of the protocol of [`run_stream`]
+pub fn parse_line(line: &str, format: Format) -> Result<Vec<Symbol>, Error> {
+    let invalid = || Error::InvalidMessage(line.to_owned());
+
+    match line.trim() {
+        "short" => Ok(vec![Symbol::Short]),
+        "long" => Ok(vec![Symbol::Long]),
+        "break" => Ok(vec![Symbol::Break(BreakKind::Group)]),
+        time => {
+            let (hour, minute) = time.split_once(':').ok_or_else(invalid)?;
+            let hour = hour.parse::<u32>().map_err(|_| invalid())?;
+            let minute = minute.parse::<u32>().map_err(|_| invalid())?;
+            let clock = Clock::new(
+                hour.try_into().map_err(|_| invalid())?,
+                minute.try_into().map_err(|_| invalid())?,
+                format,
+            );
+
+            Ok(clock.into_iter().collect())
+        }
+    }
+}
+
+/// Displays the symbols read line by line from `reader` until it is closed or `running` is
+/// cleared
+///
+/// Every line holds either a single symbol, `short`, `long` or `break`, or a time `HH:MM`
+/// which is displayed in `format`. Empty lines are ignored; other lines are rejected with
+/// a warning and skipped.
+///
+/// Reads which time out are retried, so giving the underlying socket a read timeout
+/// lets the function notice `running` being cleared while no message arrives. The
+/// watchdog of `notify` is polled after every read, see [`Watchdog`].
+pub fn run_stream<L: Led, R: BufRead>(
+    led: &mut L,
+    timing: &Timing,
+    format: Format,
+    mut reader: R,
+    running: &AtomicBool,
+    notify: Option<&dyn Notify>,
+) -> anyhow::Result<()> {
+    let mut watchdog = Watchdog::new(notify);
+    let mut line = String::new();
+
+    while running.load(Ordering::Relaxed) {
+        let read = reader.read_line(&mut line);
+        watchdog.poll()?;
+
+        match read {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        if !line.trim().is_empty() {
+            match parse_line(&line, format) {
+                Ok(symbols) => {
+                    if !display(led, timing, symbols, running)? {
+                        break;
+                    }
+                }
+                Err(e) => log::warn!("{}", e),
+            }
+        }
+
+        line.clear();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::tests::MockNotify;
+    use crate::tests::{states, MockLed, ZERO};
+    use std::time::Duration;
+
+    #[test]
+    fn lines() {
+        assert_eq!(
+            parse_line("short\n", Format::Hour12).unwrap(),
+            [Symbol::Short]
+        );
+        assert_eq!(
+            parse_line("break", Format::Hour12).unwrap(),
+            [Symbol::Break(BreakKind::Group)]
+        );
+        assert_eq!(parse_line("16:47", Format::Hour12).unwrap().len(), 8);
+
+        for line in ["dot", "16", "16:", "24:00", "12:60", "-1:30", "a:b"] {
+            assert!(parse_line(line, Format::Hour24).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn stream() {
+        let running = AtomicBool::new(true);
+        let input = "long\nshort\n\nbogus\nbreak\n4:47\nlong";
+        let mut led = MockLed::default();
+
+        let notify = MockNotify::new(Duration::from_secs(3600));
+
+        run_stream(
+            &mut led,
+            &ZERO,
+            Format::Hour12,
+            input.as_bytes(),
+            &running,
+            Some(&notify),
+        )
+        .unwrap();
+
+        // the malformed line is skipped
+        assert_eq!(led.0, states("-.=--.=-----"));
+        assert_eq!(notify.pings(), 1);
+    }
+}
@@ -0,0 +1,297 @@
+//! Sources of the time displayed by [`run`](crate::run)
+
+use crate::Error;
+use chrono::{offset::Local, DateTime, Datelike, FixedOffset, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use std::str;
+use std::time::Duration;
+
+/// A source of the current time
+pub trait TimeSource {
+    /// Returns the current (local) time
+    fn now(&self) -> NaiveDateTime;
+}
+
+/// The time of the local timezone of the system
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LocalTime;
+
+impl TimeSource for LocalTime {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+/// The time of a timezone, which may differ from the one of the system
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Zone {
+    /// The local timezone of the system, like [`LocalTime`]
+    #[default]
+    Local,
+    /// A timezone of the IANA database, e.g. "Europe/Berlin"
+    Named(Tz),
+    /// A fixed offset from UTC, e.g. "+02:00"
+    Fixed(FixedOffset),
+}
+
+impl Zone {
+    /// Returns the time of `instant` in the zone
+    pub fn at(&self, instant: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Local => instant.with_timezone(&Local).naive_local(),
+            Zone::Named(tz) => instant.with_timezone(tz).naive_local(),
+            Zone::Fixed(offset) => instant.with_timezone(offset).naive_local(),
+        }
+    }
+}
+
+impl TimeSource for Zone {
+    fn now(&self) -> NaiveDateTime {
+        self.at(Utc::now())
+    }
+}
+
+impl str::FromStr for Zone {
+    type Err = Error;
+
+    /// Parses "local", an IANA timezone name or an offset "+HH:MM" or "-HH:MM"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "local" {
+            Ok(Zone::Local)
+        } else if s.starts_with(['+', '-']) {
+            s.parse().map(Zone::Fixed).map_err(|_| Error::InvalidZone)
+        } else {
+            s.parse().map(Zone::Named).map_err(|_| Error::InvalidZone)
+        }
+    }
+}
+
+/// A time source which always returns the same time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedTime(pub NaiveDateTime);
+
+impl TimeSource for FixedTime {
+    fn now(&self) -> NaiveDateTime {
+        self.0
+    }
+}
+
+/// Returns whether `time` looks like a time which has been set
+///
+/// Boards without an RTC start at the epoch until the time has been synchronized.
+pub fn is_plausible(time: &NaiveDateTime) -> bool {
+    time.year() >= 2000
+}
+
+/// What to display while the time isn't [plausible](is_plausible)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NoTime {
+    /// Display the time anyway
+    #[default]
+    Display,
+    /// Display [`NO_TIME`](crate::NO_TIME) instead of the time
+    Pattern,
+    /// Display nothing until the time has been set
+    Wait,
+}
+
+impl str::FromStr for NoTime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "display" => Ok(NoTime::Display),
+            "pattern" => Ok(NoTime::Pattern),
+            "wait" => Ok(NoTime::Wait),
+            _ => Err(Error::InvalidNoTime),
+        }
+    }
+}
+
+/// Returns the duration from `now` until the start of the minute following `displayed`
+///
+/// See [`until_next_boundary`] for displays overrunning the following minute.
+pub fn until_next_minute(displayed: NaiveDateTime, now: NaiveDateTime) -> Duration {
+    until_next_boundary(displayed, now, Duration::from_secs(60))
+}
+
+/// The first multiple of `period` since the midnight of `time` which is after `time`
+///
+/// The following midnight is a boundary as well, even if `period` doesn't divide a day.
+fn next_boundary(time: NaiveDateTime, period: Duration) -> NaiveDateTime {
+    const DAY: u128 = 24 * 60 * 60 * 1_000_000_000;
+
+    let midnight = time.date().and_time(NaiveTime::MIN);
+    let elapsed = (time - midnight).to_std().unwrap_or_default().as_nanos();
+    let period = period.as_nanos();
+    let next = Duration::from_nanos(((elapsed / period + 1) * period).min(DAY) as u64);
+
+    midnight + chrono::Duration::from_std(next).unwrap_or_default()
+}
+
+/// Returns the duration from `now` until the next multiple of `period` after `displayed`
+///
+/// The multiples are counted from midnight, e.g. a period of 30 s aligns the start of
+/// every display to the full or half minute. If the display of `displayed` overran the
+/// next multiple, the next time is due immediately and the returned duration is zero.
+/// A display lasting longer than `period` skips to the multiple following `now` instead.
+pub fn until_next_boundary(
+    displayed: NaiveDateTime,
+    now: NaiveDateTime,
+    period: Duration,
+) -> Duration {
+    if period.is_zero() {
+        return Duration::ZERO;
+    }
+
+    let next = next_boundary(displayed, period);
+    let until = |next: NaiveDateTime| (next - now).to_std().unwrap_or(Duration::ZERO);
+
+    if now <= next {
+        until(next)
+    } else if (now - displayed).to_std().unwrap_or_default() < period {
+        Duration::ZERO
+    } else {
+        until(next_boundary(now, period))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(hour: u32, minute: u32, second: u32, milli: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 7, 4)
+            .unwrap()
+            .and_hms_milli_opt(hour, minute, second, milli)
+            .unwrap()
+    }
+
+    #[test]
+    fn plausible() {
+        assert!(!is_plausible(&NaiveDateTime::default()));
+        assert!(!is_plausible(
+            &NaiveDate::from_ymd_opt(1999, 12, 31)
+                .unwrap()
+                .and_hms_opt(23, 59, 59)
+                .unwrap()
+        ));
+        assert!(is_plausible(&at(0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn zones() {
+        // 12:45 UTC, during daylight saving time in New York
+        let instant = DateTime::from_naive_utc_and_offset(at(12, 45, 0, 0), Utc);
+        let zone = |s: &str| s.parse::<Zone>().unwrap();
+
+        assert_eq!(zone("UTC").at(instant), at(12, 45, 0, 0));
+        assert_eq!(zone("America/New_York").at(instant), at(8, 45, 0, 0));
+        assert_eq!(zone("+05:30").at(instant), at(18, 15, 0, 0));
+        assert_eq!(zone("-01:00").at(instant), at(11, 45, 0, 0));
+        assert_eq!(zone("local"), Zone::Local);
+    }
+
+    #[test]
+    fn invalid_zones() {
+        assert!("Mars/Olympus_Mons".parse::<Zone>().is_err());
+        assert!("+25:00".parse::<Zone>().is_err());
+        assert!("+5".parse::<Zone>().is_err());
+        assert!("".parse::<Zone>().is_err());
+    }
+
+    #[test]
+    fn no_time() {
+        assert_eq!("display".parse::<NoTime>().unwrap(), NoTime::Display);
+        assert_eq!("pattern".parse::<NoTime>().unwrap(), NoTime::Pattern);
+        assert_eq!("wait".parse::<NoTime>().unwrap(), NoTime::Wait);
+        assert!("never".parse::<NoTime>().is_err());
+    }
+
+    #[test]
+    fn next_minute() {
+        assert_eq!(
+            until_next_minute(at(12, 0, 10, 0), at(12, 0, 15, 500)),
+            Duration::from_millis(44_500)
+        );
+        assert_eq!(
+            until_next_minute(at(23, 59, 0, 0), at(23, 59, 0, 0)),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn next_boundary() {
+        let secs = Duration::from_secs;
+
+        // partway through a minute
+        assert_eq!(
+            until_next_boundary(at(12, 0, 10, 0), at(12, 0, 15, 500), secs(30)),
+            Duration::from_millis(14_500)
+        );
+        assert_eq!(
+            until_next_boundary(at(12, 0, 40, 0), at(12, 0, 45, 0), secs(30)),
+            secs(15)
+        );
+        assert_eq!(
+            until_next_boundary(at(12, 0, 40, 0), at(12, 0, 45, 0), secs(60)),
+            secs(15)
+        );
+        assert_eq!(
+            until_next_boundary(at(12, 7, 0, 0), at(12, 7, 20, 0), secs(300)),
+            secs(160)
+        );
+        // a period not dividing an hour is counted from midnight
+        assert_eq!(
+            until_next_boundary(at(0, 1, 0, 0), at(0, 1, 0, 0), secs(7)),
+            secs(3)
+        );
+        // the boundaries restart at midnight
+        assert_eq!(
+            until_next_boundary(at(23, 59, 58, 0), at(23, 59, 58, 0), secs(7)),
+            secs(2)
+        );
+        assert_eq!(
+            until_next_boundary(at(12, 0, 0, 0), at(12, 0, 1, 0), Duration::ZERO),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn next_boundary_long_display() {
+        let secs = Duration::from_secs;
+
+        // a display longer than a minute skips to the minute after the overrun one
+        assert_eq!(
+            until_next_minute(at(12, 0, 50, 0), at(12, 2, 5, 0)),
+            secs(55)
+        );
+        // ending exactly on the next minute displays it right away
+        assert_eq!(
+            until_next_minute(at(12, 0, 0, 0), at(12, 1, 0, 0)),
+            Duration::ZERO
+        );
+        assert_eq!(
+            until_next_boundary(at(12, 0, 20, 0), at(12, 0, 55, 0), secs(30)),
+            secs(5)
+        );
+        // shorter displays overrunning the boundary continue right away
+        assert_eq!(
+            until_next_boundary(at(12, 0, 20, 0), at(12, 0, 35, 0), secs(30)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn next_minute_overrun() {
+        assert_eq!(
+            until_next_minute(at(12, 0, 50, 0), at(12, 1, 5, 0)),
+            Duration::ZERO
+        );
+        assert_eq!(
+            until_next_minute(at(12, 0, 50, 0), at(12, 1, 0, 0)),
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,496 @@
+//! The timing parameters of a displayed clock
+
+use crate::Error;
+use morseclock::{Symbol, TimingParams};
+use std::str;
+use std::time::Duration;
+
+/// The shortest base duration and pause accepted by [`Timing::validate`] by default
+///
+/// Shorter sleeps return immediately, flickering the LED uselessly fast.
+pub const MIN_DURATION: Duration = Duration::from_millis(1);
+
+/// A duty cycle in the range (0, 1]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct DutyCycle(pub f64);
+
+impl DutyCycle {
+    /// Creates a duty cycle, failing with [`Error::InvalidDutyCycle`] outside of (0, 1]
+    pub fn new(value: f64) -> Result<Self, Error> {
+        // also rejects NaN
+        if value > 0.0 && value <= 1.0 {
+            Ok(DutyCycle(value))
+        } else {
+            Err(Error::InvalidDutyCycle)
+        }
+    }
+
+    /// The fraction of the time the LED is on
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+}
+
+impl str::FromStr for DutyCycle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s.parse()?)
+    }
+}
+
+/// The ratio between the on-durations of a short and a long blink, e.g. `1:3`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    pub short: u32,
+    pub long: u32,
+}
+
+impl str::FromStr for Ratio {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (short, long) = s.split_once(':').ok_or(Error::InvalidRatio)?;
+
+        Ok(Ratio {
+            short: short.trim().parse()?,
+            long: long.trim().parse()?,
+        })
+    }
+}
+
+/// The pause between two displayed times
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pause {
+    /// Pause for a fixed duration
+    Fixed(Duration),
+    /// Pause until the minute following the displayed time has started
+    NextMinute,
+    /// Pause until the next multiple of the duration since midnight, see
+    /// [`until_next_boundary`](crate::time::until_next_boundary)
+    Aligned(Duration),
+}
+
+impl str::FromStr for Pause {
+    type Err = Error;
+
+    /// Parses either a duration, `auto` for [`Pause::NextMinute`] or `align:` followed by
+    /// a duration for [`Pause::Aligned`]
+    ///
+    /// The durations are parsed by [`parse_duration`], an aligned one mustn't be zero.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "auto" {
+            return Ok(Pause::NextMinute);
+        }
+
+        match s.strip_prefix("align:") {
+            Some(period) => match parse_duration(period)? {
+                period if period.is_zero() => Err(Error::InvalidDuration),
+                period => Ok(Pause::Aligned(period)),
+            },
+            None => Ok(Pause::Fixed(parse_duration(s)?)),
+        }
+    }
+}
+
+/// Parses a duration in milliseconds, optionally followed by a unit of `ms` or `s`
+///
+/// Seconds may be fractional, e.g. `1.5s`.
+pub fn parse_duration(s: &str) -> Result<Duration, Error> {
+    if let Some(ms) = s.strip_suffix("ms") {
+        Ok(Duration::from_millis(ms.parse()?))
+    } else if let Some(secs) = s.strip_suffix('s') {
+        Duration::try_from_secs_f64(secs.parse()?).map_err(|_| Error::InvalidDuration)
+    } else {
+        Ok(Duration::from_millis(s.parse()?))
+    }
+}
+
+/// The durations used to blink the symbols of a clock
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timing {
+    /// Duration of a single blink, also used as break between hour and minute
+    pub base: Duration,
+    /// The pause between two displayed times
+    pub pause: Pause,
+    /// The fraction of the base duration the LED is on during a short blink
+    pub short_duty: DutyCycle,
+    /// The fraction of the base duration the LED is on during a long blink
+    pub long_duty: DutyCycle,
+    /// Duration of every break, replacing the durations derived from the base duration
+    ///
+    /// See [`Timing::without_pauses`].
+    pub collapsed_breaks: Option<Duration>,
+}
+
+impl Timing {
+    /// Creates a timing from a base duration (in milliseconds), a pause and the duty cycles
+    /// of the short and long blinks
+    pub fn new(base_ms: u64, pause: Pause, short_duty: DutyCycle, long_duty: DutyCycle) -> Self {
+        Self {
+            base: Duration::from_millis(base_ms),
+            pause,
+            short_duty,
+            long_duty,
+            collapsed_breaks: None,
+        }
+    }
+
+    /// Creates a timing from a base duration (in milliseconds), a pause and the ratio
+    /// between the on-durations of the short and long blinks
+    ///
+    /// The on-durations split the base duration proportionally, e.g. a ratio of `1:3`
+    /// keeps the LED on for a quarter of the base duration during a short blink and
+    /// three quarters during a long blink. The long blink must be longer than the short one.
+    pub fn from_ratio(base_ms: u64, pause: Pause, short: u32, long: u32) -> Result<Self, Error> {
+        if short == 0 || long <= short {
+            return Err(Error::InvalidRatio);
+        }
+
+        let total = short as f64 + long as f64;
+
+        Ok(Self::new(
+            base_ms,
+            pause,
+            DutyCycle(short as f64 / total),
+            DutyCycle(long as f64 / total),
+        ))
+    }
+
+    /// Multiplies all durations by `factor`, preserving the duty cycles
+    ///
+    /// A factor of `0.5` displays everything twice as fast. A pause until the next
+    /// minute isn't affected. Factors which aren't finite and positive are rejected.
+    pub fn scale(self, factor: f64) -> Result<Self, Error> {
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err(Error::InvalidSpeed);
+        }
+
+        let scale = |duration: Duration| duration.mul_f64(factor);
+
+        Ok(Self {
+            base: scale(self.base),
+            pause: match self.pause {
+                Pause::Fixed(pause) => Pause::Fixed(scale(pause)),
+                Pause::NextMinute => Pause::NextMinute,
+                Pause::Aligned(period) => Pause::Aligned(period),
+            },
+            collapsed_breaks: self.collapsed_breaks.map(scale),
+            ..self
+        })
+    }
+
+    /// Collapses all breaks and the pause between two times to `minimum`
+    ///
+    /// The times are displayed back to back, for e.g. a ticker sign. This trades
+    /// readability for density: the hands and times are only told apart by the
+    /// contrast between the symbols.
+    pub fn without_pauses(self, minimum: Duration) -> Self {
+        Self {
+            pause: Pause::Fixed(minimum),
+            collapsed_breaks: Some(minimum),
+            ..self
+        }
+    }
+
+    /// Checks that the base duration and a fixed or aligned pause last at least `min`
+    pub fn validate(&self, min: Duration) -> Result<(), Error> {
+        if self.base < min {
+            return Err(Error::TooShort("base duration", min));
+        }
+
+        match self.pause {
+            Pause::Fixed(pause) | Pause::Aligned(pause) if pause < min => {
+                Err(Error::TooShort("pause duration", min))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The blinks which are on for less than a millisecond, too short to be seen
+    pub fn invisible_blinks(&self) -> Vec<Symbol> {
+        [Symbol::Short, Symbol::Long]
+            .into_iter()
+            .filter(|&symbol| self.durations(symbol).0 < Duration::from_millis(1))
+            .collect()
+    }
+
+    /// The parameters of the timing shared with [`morseclock::schedule`]
+    ///
+    /// A [`BreakKind::Group`](morseclock::BreakKind::Group) break lasts for the base
+    /// duration. Collapsed breaks aren't part of them.
+    pub fn params(&self) -> TimingParams {
+        // a duty cycle created without `DutyCycle::new` may be out of range
+        let duty = |duty: DutyCycle| match duty.value() {
+            value if value.is_nan() => 0.0,
+            value => value.clamp(0.0, 1.0),
+        };
+
+        TimingParams::new(self.base, duty(self.short_duty), duty(self.long_duty), 1.0)
+            .expect("the duty cycles and a break factor of 1 are in range")
+    }
+
+    /// Returns the on and off durations used to display `symbol`
+    ///
+    /// These are the durations of [`TimingParams::durations`], unless the breaks are
+    /// collapsed.
+    pub fn durations(&self, symbol: Symbol) -> (Duration, Duration) {
+        match (symbol, self.collapsed_breaks) {
+            (Symbol::Break(_), Some(duration)) => (Duration::ZERO, duration),
+            _ => self.params().durations(symbol),
+        }
+    }
+
+    /// The total duration of displaying `symbols`, without a pause
+    pub fn display_duration<I: IntoIterator<Item = Symbol>>(&self, symbols: I) -> Duration {
+        symbols
+            .into_iter()
+            .map(|symbol| {
+                let (on, off) = self.durations(symbol);
+                on + off
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morseclock::BreakKind;
+
+    #[test]
+    fn duty_cycle() {
+        assert_eq!("0.5".parse::<DutyCycle>().unwrap(), DutyCycle(0.5));
+        assert_eq!("1".parse::<DutyCycle>().unwrap(), DutyCycle(1.0));
+        assert!("0".parse::<DutyCycle>().is_err());
+        assert!("1.5".parse::<DutyCycle>().is_err());
+        assert!("half".parse::<DutyCycle>().is_err());
+    }
+
+    #[test]
+    fn duty_cycle_new() {
+        assert!(matches!(DutyCycle::new(0.0), Err(Error::InvalidDutyCycle)));
+        assert_eq!(DutyCycle::new(1.0).unwrap().value(), 1.0);
+        assert!(matches!(
+            DutyCycle::new(1.0001),
+            Err(Error::InvalidDutyCycle)
+        ));
+        assert_eq!(
+            DutyCycle::new(f64::MIN_POSITIVE).unwrap().value(),
+            f64::MIN_POSITIVE
+        );
+        assert!(DutyCycle::new(-0.5).is_err());
+        assert!(DutyCycle::new(f64::NAN).is_err());
+        assert!("NaN".parse::<DutyCycle>().is_err());
+    }
+
+    #[test]
+    fn durations() {
+        let ms = Duration::from_millis;
+        let timing = Timing::new(500, Pause::NextMinute, DutyCycle(0.1), DutyCycle(0.5));
+
+        assert_eq!(timing.durations(Symbol::Short), (ms(50), ms(450)));
+        assert_eq!(timing.durations(Symbol::Long), (ms(250), ms(250)));
+    }
+
+    #[test]
+    fn break_durations() {
+        let ms = Duration::from_millis;
+        let timing = Timing::new(700, Pause::NextMinute, DutyCycle(0.1), DutyCycle(0.5));
+
+        // the digits 4 and 2, each followed by a break
+        let offs: Vec<_> = crate::identification(42)
+            .into_iter()
+            .chain([Symbol::Break(BreakKind::Group)])
+            .filter(|symbol| matches!(symbol, Symbol::Break(_)))
+            .map(|symbol| timing.durations(symbol))
+            .collect();
+        assert_eq!(offs, [(Duration::ZERO, ms(300)), (Duration::ZERO, ms(700))]);
+
+        assert_eq!(
+            timing.durations(Symbol::Break(BreakKind::Symbol)),
+            (Duration::ZERO, ms(100))
+        );
+    }
+
+    #[test]
+    fn morse_timed() {
+        use morseclock::{Clock, Format, MorseExt};
+
+        let timing = Timing::new(500, Pause::NextMinute, DutyCycle(0.2), DutyCycle(0.6));
+        let clock = Clock::new(
+            4.try_into().unwrap(),
+            45.try_into().unwrap(),
+            Format::Hour12,
+        );
+        // a break within a character, which neither the identification nor a clock has
+        let symbols = || {
+            crate::identification(42)
+                .into_iter()
+                .chain([
+                    Symbol::Short,
+                    Symbol::Break(BreakKind::Symbol),
+                    Symbol::Short,
+                ])
+                .chain(clock)
+        };
+
+        let timed: Vec<_> = symbols()
+            .morse_timed(timing.base, 0.2, 0.6, 1.0)
+            .unwrap()
+            .collect();
+        // the durations blinked by the display loop
+        let expected: Vec<_> = symbols()
+            .map(|symbol| {
+                let (on, off) = timing.durations(symbol);
+                let glyph: String = [symbol].into_iter().morse().collect();
+                (glyph, on, off)
+            })
+            .collect();
+
+        let timed: Vec<_> = timed
+            .into_iter()
+            .map(|(glyph, on, off)| (glyph.to_owned(), on, off))
+            .collect();
+        assert_eq!(timed, expected);
+        assert!(timed.iter().any(|(glyph, ..)| glyph == " "));
+        assert!(timed.contains(&(String::new(), Duration::ZERO, timing.base / 7)));
+    }
+
+    #[test]
+    fn without_pauses() {
+        let ms = Duration::from_millis;
+        let timing = Timing::new(700, Pause::NextMinute, DutyCycle(0.1), DutyCycle(0.5))
+            .without_pauses(ms(20));
+
+        assert_eq!(timing.pause, Pause::Fixed(ms(20)));
+        for kind in [BreakKind::Symbol, BreakKind::Digit, BreakKind::Group] {
+            assert_eq!(
+                timing.durations(Symbol::Break(kind)),
+                (Duration::ZERO, ms(20))
+            );
+        }
+        // the symbols themselves are unchanged
+        assert_eq!(timing.durations(Symbol::Long), (ms(350), ms(350)));
+    }
+
+    #[test]
+    fn display_duration() {
+        let timing = Timing::new(100, Pause::NextMinute, DutyCycle(0.1), DutyCycle(0.5));
+        let symbols = [Symbol::Long, Symbol::Break(BreakKind::Group), Symbol::Short];
+
+        assert_eq!(timing.display_duration(symbols), Duration::from_millis(300));
+        assert_eq!(timing.display_duration([]), Duration::ZERO);
+    }
+
+    #[test]
+    fn ratio() {
+        let ratio = "1:3".parse::<Ratio>().unwrap();
+        assert_eq!(ratio, Ratio { short: 1, long: 3 });
+
+        let ms = Duration::from_millis;
+        let timing = Timing::from_ratio(400, Pause::NextMinute, ratio.short, ratio.long).unwrap();
+        assert_eq!(timing.durations(Symbol::Short), (ms(100), ms(300)));
+        assert_eq!(timing.durations(Symbol::Long), (ms(300), ms(100)));
+    }
+
+    #[test]
+    fn invalid_ratio() {
+        for ratio in ["", "1", "1:", ":3", "a:b", "1:3:5", "-1:3", "1/3"] {
+            assert!(ratio.parse::<Ratio>().is_err(), "{}", ratio);
+        }
+
+        for (short, long) in [(0, 3), (3, 3), (3, 1), (0, 0)] {
+            assert!(Timing::from_ratio(400, Pause::NextMinute, short, long).is_err());
+        }
+    }
+
+    #[test]
+    fn scale() {
+        let ms = Duration::from_millis;
+        let timing = Timing::new(500, Pause::Fixed(ms(2500)), DutyCycle(0.1), DutyCycle(0.5));
+        let half = timing.scale(0.5).unwrap();
+
+        assert_eq!(half.base, ms(250));
+        assert_eq!(half.pause, Pause::Fixed(ms(1250)));
+        assert_eq!(half.durations(Symbol::Short), (ms(25), ms(225)));
+        assert_eq!(half.durations(Symbol::Long), (ms(125), ms(125)));
+
+        assert_eq!(timing.scale(1.0).unwrap(), timing);
+        assert_eq!(timing.scale(2.0).unwrap().base, ms(1000));
+    }
+
+    #[test]
+    fn invalid_scale() {
+        let timing = Timing::new(500, Pause::NextMinute, DutyCycle(0.1), DutyCycle(0.5));
+
+        for factor in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(timing.scale(factor).is_err());
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let min = Duration::from_millis(1);
+        let timing = |base, pause| Timing::new(base, pause, DutyCycle(0.1), DutyCycle(0.5));
+
+        assert!(timing(1, Pause::Fixed(min)).validate(min).is_ok());
+        assert!(timing(1, Pause::NextMinute).validate(min).is_ok());
+        assert!(matches!(
+            timing(0, Pause::NextMinute).validate(min),
+            Err(Error::TooShort("base duration", _))
+        ));
+        assert!(matches!(
+            timing(100, Pause::Fixed(Duration::ZERO)).validate(min),
+            Err(Error::TooShort("pause duration", _))
+        ));
+        assert!(timing(100, Pause::NextMinute)
+            .validate(Duration::from_millis(200))
+            .is_err());
+    }
+
+    #[test]
+    fn invisible_blinks() {
+        let timing = |base| Timing::new(base, Pause::NextMinute, DutyCycle(0.1), DutyCycle(0.5));
+
+        assert!(timing(10).invisible_blinks().is_empty());
+        // the short blink is on for 0.5 ms, which is rounded to 0
+        assert_eq!(timing(5).invisible_blinks(), [Symbol::Short]);
+        assert_eq!(timing(1).invisible_blinks(), [Symbol::Short, Symbol::Long]);
+    }
+
+    #[test]
+    fn duration() {
+        let ms = Duration::from_millis;
+
+        assert_eq!(parse_duration("200ms").unwrap(), ms(200));
+        assert_eq!(parse_duration("1s").unwrap(), ms(1000));
+        assert_eq!(parse_duration("1.5s").unwrap(), ms(1500));
+        assert_eq!(parse_duration("300").unwrap(), ms(300));
+
+        for invalid in ["", "ms", "-1s", "1m", "1.5ms"] {
+            assert!(parse_duration(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn pause() {
+        assert_eq!("auto".parse::<Pause>().unwrap(), Pause::NextMinute);
+        assert_eq!(
+            "2500".parse::<Pause>().unwrap(),
+            Pause::Fixed(Duration::from_millis(2500))
+        );
+        assert_eq!(
+            "align:30s".parse::<Pause>().unwrap(),
+            Pause::Aligned(Duration::from_secs(30))
+        );
+        assert_eq!(
+            "align:500".parse::<Pause>().unwrap(),
+            Pause::Aligned(Duration::from_millis(500))
+        );
+        assert!("-1".parse::<Pause>().is_err());
+        assert!("soon".parse::<Pause>().is_err());
+        assert!("align:0".parse::<Pause>().is_err());
+        assert!("align:".parse::<Pause>().is_err());
+    }
+}
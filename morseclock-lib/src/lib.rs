@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code)]
 
 //! # morseclock - Yet another not-so-intuitive clock
@@ -37,6 +38,9 @@
 //! On the minute-hand, a single `'-'` stands for a increment of 15 minutes (a quarter rotation of the hand), starting at the 0 o'clock position.
 //! A single `'.'` stands for an increment of 5 minutes.
 //!
+//! A hand at the 0 o'clock position is a single `'-'`. A zero is therefore never an empty
+//! group: each hand blinks at least once and can't be mistaken for the `'='` break.
+//!
 //!
 //! ## Some time examples on the 12 hour clock
 //!
@@ -48,38 +52,88 @@
 //! All minutes are rounded to 5 minutes, therefore the str representations of the following times are equal
 //!
 //! 00:00 is 00:01 is .. is 00:04
+//!
+//! # Features
+//!
+//! The encoding works without the standard library, e.g. on a microcontroller.
+//!
+//! - `alloc`: [`SymbolSequence`], [`ClockBuilder`], [`quinary`], [`rle`], [`schedule`],
+//!   [`schedule_with`] and [`MorseExt::cw_text`], which need an allocator
+//! - `std` (default): implies `alloc` and implements [`std::error::Error`] for [`Error`]
+//! - `chrono`: creates a [`Clock`] from the times of [`chrono`], see [`Clock::from_time`]
 
-use std::error;
-use std::fmt;
-use std::iter;
-use std::marker::PhantomData;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use core::iter;
+use core::marker::PhantomData;
+use core::str;
+use core::time::Duration;
 
 /// A collection of errors which can happen
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Error {
     /// The given value is invalid for a hand
     InvalidHandValue,
+    /// The given character doesn't describe a [`Symbol`]
+    InvalidSymbol(char),
+    /// A digit encoding doesn't encode every digit from 0 to 9
+    InvalidEncoding,
+    /// A symbol stream doesn't describe a time
+    DecodeError,
+    /// The given string doesn't describe a [`Symbol`]
+    InvalidToken,
+    /// The given string doesn't describe a [`Format`]
+    InvalidFormat,
+    /// An integer doesn't fit into the type it is converted into, e.g. a `u64` into a `u32`
+    OutOfRange,
+    /// A duty cycle isn't in the range [0, 1] or a break doesn't last a valid duration
+    InvalidTiming,
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::InvalidHandValue => f.write_str("Invalid hand value"),
+            Self::InvalidSymbol(c) => write!(f, "Invalid symbol {:?}", c),
+            Self::InvalidEncoding => f.write_str("Invalid encoding"),
+            Self::DecodeError => f.write_str("Symbols don't describe a time"),
+            Self::InvalidToken => f.write_str("Invalid symbol token"),
+            Self::InvalidFormat => f.write_str("Invalid format"),
+            Self::OutOfRange => f.write_str("Integer out of range"),
+            Self::InvalidTiming => f.write_str("Invalid timing"),
         }
     }
 }
 
-impl error::Error for Error {}
+impl From<core::num::TryFromIntError> for Error {
+    fn from(_: core::num::TryFromIntError) -> Self {
+        Self::OutOfRange
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
 
 /// A trait describing the properties of a clock hand
 pub trait ClockHand {
     /// The granularity of the given hand
     const GRANULARITY: u8;
+    /// The minimum value of the given hand
+    const MIN: u8 = 0;
     /// The maximum value of the given hand
     const MAX: u8;
 
     /// Converts a hand value into a number of long and short symbols
     fn to_long_short(value: u8) -> (u8, u8);
+
+    /// Converts a number of long and short symbols back into a hand value
+    ///
+    /// Returns `None` if no hand value is converted into these symbols.
+    fn from_long_short(long: u8, short: u8) -> Option<u8>;
 }
 
 /// Hour marker struct for [`Hand`]
@@ -93,6 +147,15 @@ impl ClockHand for Hour {
     fn to_long_short(value: u8) -> (u8, u8) {
         (value / 3 + 1, value % 3)
     }
+
+    fn from_long_short(long: u8, short: u8) -> Option<u8> {
+        if long == 0 || short >= 3 {
+            return None;
+        }
+
+        // 24 is displayed on the 24 hour clock with `Midnight::TwentyFour`
+        Some((long - 1).checked_mul(3)? + short).filter(|&value| value <= Self::MAX)
+    }
 }
 
 /// Minute marker struct for [`Hand`]
@@ -106,12 +169,87 @@ impl ClockHand for Minute {
     fn to_long_short(value: u8) -> (u8, u8) {
         (value / 15 + 1, value % 15 / Self::GRANULARITY)
     }
+
+    fn from_long_short(long: u8, short: u8) -> Option<u8> {
+        if long == 0 || short >= 3 {
+            return None;
+        }
+
+        Some((long - 1).checked_mul(15)? + short * Self::GRANULARITY)
+            .filter(|&value| value < Self::MAX)
+    }
+}
+
+/// Second marker struct for [`Hand`]
+///
+/// Seconds are encoded like minutes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Second;
+
+impl ClockHand for Second {
+    const GRANULARITY: u8 = Minute::GRANULARITY;
+    const MAX: u8 = Minute::MAX;
+
+    fn to_long_short(value: u8) -> (u8, u8) {
+        Minute::to_long_short(value)
+    }
+
+    fn from_long_short(long: u8, short: u8) -> Option<u8> {
+        Minute::from_long_short(long, short)
+    }
+}
+
+/// Month marker struct for [`Hand`], from 1 to 12
+///
+/// Unlike the minutes, every month is encoded distinctly, like the hours
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Month;
+
+impl ClockHand for Month {
+    const GRANULARITY: u8 = 1;
+    const MIN: u8 = 1;
+    const MAX: u8 = 13;
+
+    fn to_long_short(value: u8) -> (u8, u8) {
+        Hour::to_long_short(value)
+    }
+
+    fn from_long_short(long: u8, short: u8) -> Option<u8> {
+        Hour::from_long_short(long, short).filter(|value| (Self::MIN..Self::MAX).contains(value))
+    }
+}
+
+/// Day of the month marker struct for [`Hand`], from 1 to 31
+///
+/// Every day is encoded distinctly, like the hours
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Day;
+
+impl ClockHand for Day {
+    const GRANULARITY: u8 = 1;
+    const MIN: u8 = 1;
+    const MAX: u8 = 32;
+
+    fn to_long_short(value: u8) -> (u8, u8) {
+        Hour::to_long_short(value)
+    }
+
+    fn from_long_short(long: u8, short: u8) -> Option<u8> {
+        if long == 0 || short >= 3 {
+            return None;
+        }
+
+        let value = (long - 1).checked_mul(3)? + short;
+
+        (Self::MIN..Self::MAX).contains(&value).then_some(value)
+    }
 }
 
 /// A clock hand
 ///
 /// The only way to construct a hand is via the [`TryFrom`]/[`TryInto`] implementations
-/// for [`Hand<Hour>`] and [`Hand<Minute>`]
+/// for [`Hand<Hour>`], [`Hand<Minute>`], [`Hand<Second>`], [`Hand<Month>`] and
+/// [`Hand<Day>`]
 ///
 /// # Example
 /// ```
@@ -138,7 +276,7 @@ impl<H: ClockHand> TryFrom<u32> for Hand<H> {
     type Error = Error;
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
-        if value >= H::MAX as u32 {
+        if value < H::MIN as u32 || value >= H::MAX as u32 {
             return Err(Error::InvalidHandValue);
         }
 
@@ -187,15 +325,233 @@ impl<H> Iterator for HandIter<H> {
     }
 }
 
+/// The length of a [`Symbol::Break`], following the spacing of international morse code
+///
+/// Ordered from the shortest to the longest break.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BreakKind {
+    /// The gap between two symbols of a character, one unit in morse code
+    Symbol,
+    /// The gap between two characters (or digits), three units in morse code
+    Digit,
+    /// The gap between two words, seven units in morse code
+    ///
+    /// A [`Clock`] separates its hands by this break.
+    Group,
+}
+
 /// The symbols used to describe a time
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Symbol {
     /// The break symbol (used to distinguish between hour and minute)
-    Break,
+    Break(BreakKind),
     /// The short symbol
     Short,
     /// Thoe long symbol
     Long,
+    /// Whether a time of a 12 hour clock is before or after noon
+    ///
+    /// Only emitted by a [`Format::Hour12`] clock with [`Clock::meridiem`] set.
+    Meridiem(Meridiem),
+}
+
+/// The half of the day of a time, see [`Symbol::Meridiem`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Meridiem {
+    /// Before noon
+    Am,
+    /// After noon
+    Pm,
+}
+
+impl TryFrom<char> for Symbol {
+    type Error = Error;
+
+    /// Converts the characters produced by [`MorseExt::morse`] back into a symbol
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '=' => Ok(Symbol::Break(BreakKind::Group)),
+            ' ' => Ok(Symbol::Break(BreakKind::Digit)),
+            '.' => Ok(Symbol::Short),
+            '-' => Ok(Symbol::Long),
+            'A' => Ok(Symbol::Meridiem(Meridiem::Am)),
+            'P' => Ok(Symbol::Meridiem(Meridiem::Pm)),
+            c => Err(Error::InvalidSymbol(c)),
+        }
+    }
+}
+
+/// Writes a symbol as a token: `S`, `L` and `B` for short, long and group break
+///
+/// The shorter breaks are `BD` (digit) and `BS` (symbol), the meridiem indicators
+/// `AM` and `PM`.
+///
+/// # Example
+/// ```
+/// # use morseclock::Symbol;
+/// #
+/// let tokens: Vec<_> = "S L B S".split(' ').map(str::parse).collect::<Result<_, _>>()?;
+/// let line: Vec<_> = tokens.iter().map(Symbol::to_string).collect();
+///
+/// assert_eq!(line.join(" "), "S L B S");
+/// # Ok::<(), morseclock::Error>(())
+/// ```
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Symbol::Short => "S",
+            Symbol::Long => "L",
+            Symbol::Break(BreakKind::Group) => "B",
+            Symbol::Break(BreakKind::Digit) => "BD",
+            Symbol::Break(BreakKind::Symbol) => "BS",
+            Symbol::Meridiem(Meridiem::Am) => "AM",
+            Symbol::Meridiem(Meridiem::Pm) => "PM",
+        })
+    }
+}
+
+impl str::FromStr for Symbol {
+    type Err = Error;
+
+    /// Parses the tokens written by the [`Display`](fmt::Display) implementation
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "S" => Ok(Symbol::Short),
+            "L" => Ok(Symbol::Long),
+            "B" => Ok(Symbol::Break(BreakKind::Group)),
+            "BD" => Ok(Symbol::Break(BreakKind::Digit)),
+            "BS" => Ok(Symbol::Break(BreakKind::Symbol)),
+            "AM" => Ok(Symbol::Meridiem(Meridiem::Am)),
+            "PM" => Ok(Symbol::Meridiem(Meridiem::Pm)),
+            _ => Err(Error::InvalidToken),
+        }
+    }
+}
+
+/// An arbitrary sequence of [`Symbol`]s
+///
+/// # Example
+/// ```
+/// # use morseclock::{BreakKind, Symbol, SymbolSequence};
+/// #
+/// let sequence: SymbolSequence = "..-=".parse().unwrap();
+///
+/// assert_eq!(
+///     sequence.0,
+///     [Symbol::Short, Symbol::Short, Symbol::Long, Symbol::Break(BreakKind::Group)]
+/// );
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SymbolSequence(pub Vec<Symbol>);
+
+#[cfg(feature = "alloc")]
+impl str::FromStr for SymbolSequence {
+    type Err = Error;
+
+    /// Parses a string of the characters produced by [`MorseExt::morse`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars().map(Symbol::try_from).collect()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FromIterator<Symbol> for SymbolSequence {
+    fn from_iter<I: IntoIterator<Item = Symbol>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl IntoIterator for SymbolSequence {
+    type Item = Symbol;
+    type IntoIter = alloc::vec::IntoIter<Symbol>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> IntoIterator for &'a SymbolSequence {
+    type Item = Symbol;
+    type IntoIter = iter::Copied<core::slice::Iter<'a, Symbol>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter().copied()
+    }
+}
+
+/// A sequence marking the start of a transmission
+///
+/// The preamble (`"-.-.-="`) can't appear within the symbols of a [`Clock`], because a
+/// short symbol is never followed by a long one on the same hand.
+/// See [`find_preamble`] to locate it.
+pub const PREAMBLE: [Symbol; 6] = [
+    Symbol::Long,
+    Symbol::Short,
+    Symbol::Long,
+    Symbol::Short,
+    Symbol::Long,
+    Symbol::Break(BreakKind::Group),
+];
+
+/// Locates the first [`PREAMBLE`] in `symbols`
+///
+/// Returns the index of the first symbol following the preamble, or `None` if
+/// `symbols` doesn't contain a preamble.
+///
+/// # Example
+/// ```
+/// # use morseclock::{find_preamble, Symbol, PREAMBLE};
+/// #
+/// let mut symbols = vec![Symbol::Short, Symbol::Long];
+/// symbols.extend(PREAMBLE);
+/// symbols.push(Symbol::Long);
+///
+/// assert_eq!(find_preamble(&symbols), Some(8));
+/// ```
+pub fn find_preamble(symbols: &[Symbol]) -> Option<usize> {
+    symbols
+        .windows(PREAMBLE.len())
+        .position(|window| window == PREAMBLE)
+        .map(|pos| pos + PREAMBLE.len())
+}
+
+/// A sequence marking the end of a transmission
+///
+/// The end of transmission is the ITU prosign SK (`"...-.-"`), preceded by a break to
+/// separate it from the last hand. See [`find_eot`] to locate it.
+pub const EOT: [Symbol; 7] = [
+    Symbol::Break(BreakKind::Group),
+    Symbol::Short,
+    Symbol::Short,
+    Symbol::Short,
+    Symbol::Long,
+    Symbol::Short,
+    Symbol::Long,
+];
+
+/// Locates the first [`EOT`] in `symbols`
+///
+/// Returns the index of the first symbol of the end of transmission, which is the
+/// end of the transmitted data, or `None` if `symbols` doesn't contain it.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// # use morseclock::{find_eot, find_preamble, SymbolSequence};
+/// #
+/// let symbols: SymbolSequence = "..-.-.-=---=...-.-..".parse().unwrap();
+///
+/// let start = find_preamble(&symbols.0).unwrap();
+/// let end = find_eot(&symbols.0[start..]).unwrap() + start;
+///
+/// assert_eq!(&symbols.0[start..end], &"---".parse::<SymbolSequence>().unwrap().0);
+/// # }
+/// ```
+pub fn find_eot(symbols: &[Symbol]) -> Option<usize> {
+    symbols.windows(EOT.len()).position(|window| window == EOT)
 }
 
 /// The output format of the clock, either 12 or 24 hours
@@ -205,12 +561,52 @@ pub enum Format {
     Hour24,
 }
 
+/// Writes the format as `12` or `24`
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Hour12 => f.write_str("12"),
+            Format::Hour24 => f.write_str("24"),
+        }
+    }
+}
+
+impl str::FromStr for Format {
+    type Err = Error;
+
+    /// Parses `12` or `Hour12` and `24` or `Hour24`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "12" | "Hour12" => Ok(Format::Hour12),
+            "24" | "Hour24" => Ok(Format::Hour24),
+            _ => Err(Error::InvalidFormat),
+        }
+    }
+}
+
+/// How midnight is displayed by [`Format::Hour24`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Midnight {
+    /// Display midnight as hour 0 (`-`)
+    #[default]
+    Zero,
+    /// Display midnight as hour 24 (`---------`), the end of the previous day
+    TwentyFour,
+}
+
 /// A Clock which can produce a morse-like sequence of dits and dashes
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Clock {
     pub hour: Hand<Hour>,
     pub minute: Hand<Minute>,
+    pub second: Option<Hand<Second>>,
     pub format: Format,
+    /// Only used by [`Format::Hour24`]
+    pub midnight: Midnight,
+    /// Emit a [`Symbol::Meridiem`] after the time, only used by [`Format::Hour12`]
+    pub meridiem: bool,
+    /// The month and day displayed before the time
+    pub date: Option<(Hand<Month>, Hand<Day>)>,
 }
 
 impl Clock {
@@ -218,123 +614,2249 @@ impl Clock {
         Self {
             hour,
             minute,
+            second: None,
             format,
+            midnight: Midnight::Zero,
+            meridiem: false,
+            date: None,
         }
     }
-}
 
-impl IntoIterator for Clock {
-    type Item = Symbol;
-    type IntoIter = ClockIter;
+    /// Creates a clock which also displays the seconds
+    ///
+    /// The seconds are displayed like the minutes, separated from them by another
+    /// [`Symbol::Break`].
+    ///
+    /// # Example
+    /// ```
+    /// # use morseclock::{Clock, Format, MorseExt};
+    /// #
+    /// let hour = 9.try_into().unwrap();
+    /// let minute = 41.try_into().unwrap();
+    /// let second = 7.try_into().unwrap();
+    ///
+    /// let time: String = Clock::with_seconds(hour, minute, second, Format::Hour12)
+    ///     .into_iter()
+    ///     .morse()
+    ///     .collect();
+    ///
+    /// assert_eq!(time, "----=---..=-.");
+    /// ```
+    pub fn with_seconds(
+        hour: Hand<Hour>,
+        minute: Hand<Minute>,
+        second: Hand<Second>,
+        format: Format,
+    ) -> Self {
+        Self {
+            second: Some(second),
+            ..Self::new(hour, minute, format)
+        }
+    }
 
-    fn into_iter(mut self) -> Self::IntoIter {
-        if self.format == Format::Hour12 {
-            self.hour.value %= 12;
+    /// Returns all symbols of the clock at once, like collecting its iterator
+    ///
+    /// # Example
+    /// ```
+    /// # use morseclock::{Clock, Format, Symbol};
+    /// #
+    /// let clock = Clock::new(1.try_into().unwrap(), 0.try_into().unwrap(), Format::Hour12);
+    /// let symbols = clock.symbols();
+    ///
+    /// assert_eq!(symbols.len(), 4);
+    /// assert_eq!(symbols, clock.into_iter().collect::<Vec<Symbol>>());
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn symbols(&self) -> Vec<Symbol> {
+        (*self).into_iter().collect()
+    }
+
+    /// Creates a clock which displays the date before the time
+    ///
+    /// The month and the day are displayed like the hour, each followed by a
+    /// [`BreakKind::Group`] break.
+    ///
+    /// # Example
+    /// ```
+    /// # use morseclock::{Clock, Format, MorseExt};
+    /// #
+    /// let clock = Clock::with_date(
+    ///     7.try_into()?,
+    ///     4.try_into()?,
+    ///     12.try_into()?,
+    ///     0.try_into()?,
+    ///     Format::Hour24,
+    /// );
+    ///
+    /// assert_eq!(clock.into_iter().morse().collect::<String>(), "---.=--.=-----=-");
+    /// # Ok::<(), morseclock::Error>(())
+    /// ```
+    pub fn with_date(
+        month: Hand<Month>,
+        day: Hand<Day>,
+        hour: Hand<Hour>,
+        minute: Hand<Minute>,
+        format: Format,
+    ) -> Self {
+        Self {
+            date: Some((month, day)),
+            ..Self::new(hour, minute, format)
         }
+    }
 
-        ClockIter(
-            self.hour
-                .into_iter()
-                .chain(iter::once(Symbol::Break))
-                .chain(self.minute.into_iter()),
-        )
+    /// Creates a clock displaying the hour and minute of `time`
+    ///
+    /// The hour and minute are validated like by converting them into a [`Hand`], which
+    /// a chrono time always passes. [`Clock::try_from`] uses [`Format::Hour12`].
+    ///
+    /// # Example
+    /// ```
+    /// # use chrono::NaiveTime;
+    /// # use morseclock::{Clock, Format};
+    /// #
+    /// let time = NaiveTime::from_hms_opt(16, 47, 0).unwrap();
+    /// let clock = Clock::new(16.try_into()?, 47.try_into()?, Format::Hour24);
+    ///
+    /// assert_eq!(Clock::from_time(&time, Format::Hour24)?, clock);
+    /// # Ok::<(), morseclock::Error>(())
+    /// ```
+    #[cfg(feature = "chrono")]
+    pub fn from_time<T: chrono::Timelike>(time: &T, format: Format) -> Result<Self, Error> {
+        Ok(Self::new(
+            time.hour().try_into()?,
+            time.minute().try_into()?,
+            format,
+        ))
+    }
+
+    /// Creates a builder for a clock with a custom encoding
+    #[cfg(feature = "alloc")]
+    pub fn builder(hour: Hand<Hour>, minute: Hand<Minute>, format: Format) -> ClockBuilder {
+        ClockBuilder {
+            clock: Self::new(hour, minute, format),
+            encoder: None,
+        }
+    }
+
+    /// The value displayed by the hour hand, according to the format
+    fn hour_value(&self) -> u8 {
+        match self.format {
+            // the hand points up at 12 o'clock, like at 0 o'clock on the 24 hour clock
+            Format::Hour12 => to_display_hour(self.hour.value, self.format) % 12,
+            Format::Hour24 if self.midnight == Midnight::TwentyFour && self.hour.value == 0 => {
+                Hour::MAX
+            }
+            Format::Hour24 => self.hour.value,
+        }
     }
 }
 
-/// An iterator over a [`Clock`] which produces a series of [`Symbol`]s
-pub struct ClockIter(
-    iter::Chain<iter::Chain<HandIter<Hour>, iter::Once<Symbol>>, HandIter<Minute>>,
-);
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveTime> for Clock {
+    type Error = Error;
 
-impl Iterator for ClockIter {
-    type Item = Symbol;
+    /// Creates a 12 hour clock, see [`Clock::from_time`]
+    fn try_from(time: chrono::NaiveTime) -> Result<Self, Self::Error> {
+        Self::from_time(&time, Format::Hour12)
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.next()
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> TryFrom<chrono::DateTime<Tz>> for Clock {
+    type Error = Error;
+
+    /// Creates a 12 hour clock of the local time of `time`, see [`Clock::from_time`]
+    fn try_from(time: chrono::DateTime<Tz>) -> Result<Self, Self::Error> {
+        Self::from_time(&time, Format::Hour12)
     }
 }
 
-/// An extension trait for iterators which yield [`Symbol`]s
-pub trait MorseExt {
-    type Output;
-    fn morse(self) -> Self::Output;
+/// The hour of the day `hour` as read on a clock in `format`
+///
+/// On the 12 hour clock both midnight and noon are 12 and the afternoon starts again at
+/// 1, the hours of the 24 hour clock are the hours of the day.
+fn to_display_hour(hour: u8, format: Format) -> u8 {
+    match format {
+        Format::Hour12 if hour.is_multiple_of(12) => 12,
+        Format::Hour12 => hour % 12,
+        Format::Hour24 => hour,
+    }
 }
 
-/// An iterator adapter which produces a series of morsecode-like symbols
+/// A builder for the symbols of a [`Clock`] with a custom encoding
 ///
-/// See [`MorseExt`]
-pub struct Morse<I>(I);
+/// Instead of the positions of the hands, the values of the hands can be encoded as
+/// their two decimal digits, each encoded by a table of symbols and separated by a
+/// [`BreakKind::Digit`] break, or as a whole by a [`value_encoder`](Self::value_encoder)
+/// like [`quinary`]. The hands are separated by a [`BreakKind::Group`] break. Without
+/// an encoding, the symbols are the ones of the [`Clock`] itself.
+///
+/// # Example
+/// ```
+/// # use morseclock::{Clock, Format, MorseExt, Symbol};
+/// #
+/// // "cut numbers", shortened morse digits
+/// let cut = |digit| match digit {
+///     0 => vec![Symbol::Long],
+///     9 => vec![Symbol::Long, Symbol::Short],
+///     d => (0..d).map(|_| Symbol::Short).chain([Symbol::Long]).collect(),
+/// };
+///
+/// let symbols = Clock::builder(9.try_into()?, 41.try_into()?, Format::Hour24)
+///     .digit_encoder(cut)?
+///     .build();
+///
+/// assert_eq!(symbols.morse().collect::<String>(), "- -.=....- .-");
+/// # Ok::<(), morseclock::Error>(())
+/// ```
+#[cfg(feature = "alloc")]
+pub struct ClockBuilder {
+    clock: Clock,
+    /// The symbols of every hand value from 0 to 59
+    encoder: Option<Vec<Vec<Symbol>>>,
+}
 
-impl<I> MorseExt for I
-where
-    I: Iterator<Item = Symbol>,
-{
-    type Output = Morse<I>;
+#[cfg(feature = "alloc")]
+impl ClockBuilder {
+    /// Also displays the seconds, like [`Clock::with_seconds`]
+    pub fn second(mut self, second: Hand<Second>) -> Self {
+        self.clock.second = Some(second);
+        self
+    }
 
-    fn morse(self) -> Self::Output {
-        Morse(self)
+    /// Encodes the digits with `table`, which holds the symbols of every digit from 0 to 9
+    ///
+    /// Fails with [`Error::InvalidEncoding`] unless `table` holds exactly 10 digits, each
+    /// encoded by at least one symbol.
+    pub fn digits<T: AsRef<[Symbol]>>(self, table: &[T]) -> Result<Self, Error> {
+        if table.len() != 10 {
+            return Err(Error::InvalidEncoding);
+        }
+
+        self.digit_encoder(|digit| table[usize::from(digit)].as_ref().to_vec())
     }
-}
 
-impl<I> Iterator for Morse<I>
-where
-    I: Iterator<Item = Symbol>,
-{
-    type Item = char;
+    /// Encodes the digits with the symbols returned by `encoder` for every digit from 0 to 9
+    ///
+    /// Fails with [`Error::InvalidEncoding`] if a digit is encoded by no symbols.
+    pub fn digit_encoder<F: Fn(u8) -> Vec<Symbol>>(self, encoder: F) -> Result<Self, Error> {
+        let table: Vec<_> = (0..10).map(encoder).collect();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.0.next() {
-            Some(Symbol::Break) => Some('='),
-            Some(Symbol::Short) => Some('.'),
-            Some(Symbol::Long) => Some('-'),
-            None => None,
+        if table.iter().any(|symbols| symbols.is_empty()) {
+            return Err(Error::InvalidEncoding);
         }
+
+        self.value_encoder(|value| {
+            let mut symbols = table[usize::from(value / 10)].clone();
+            symbols.push(Symbol::Break(BreakKind::Digit));
+            symbols.extend(&table[usize::from(value % 10)]);
+            symbols
+        })
     }
-}
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
+    /// Encodes the value of every hand with the symbols returned by `encoder`
+    ///
+    /// `encoder` is called for every value from 0 to 59, failing with
+    /// [`Error::InvalidEncoding`] if a value is encoded by no symbols.
+    ///
+    /// # Example
+    /// ```
+    /// # use morseclock::{quinary, Clock, Format, MorseExt};
+    /// #
+    /// let symbols = Clock::builder(9.try_into()?, 41.try_into()?, Format::Hour24)
+    ///     .value_encoder(quinary)?
+    ///     .build();
+    ///
+    /// // 9 is 14 and 41 is 131 in base 5
+    /// assert_eq!(symbols.morse().collect::<String>(), ". ....=. ... .");
+    /// # Ok::<(), morseclock::Error>(())
+    /// ```
+    pub fn value_encoder<F: Fn(u8) -> Vec<Symbol>>(mut self, encoder: F) -> Result<Self, Error> {
+        let table: Vec<_> = (0..Minute::MAX).map(encoder).collect();
 
-    macro_rules! eq {
-        (($hour:expr, $minute:expr), $result_h12:expr) => {
-            let hour = $hour.try_into().unwrap();
-            let minute = $minute.try_into().unwrap();
+        if table.iter().any(|symbols| symbols.is_empty()) {
+            return Err(Error::InvalidEncoding);
+        }
 
-            let dashes: String = Clock::new(hour, minute, Format::Hour12)
-                .into_iter()
-                .morse()
-                .collect();
+        self.encoder = Some(table);
+        Ok(self)
+    }
 
-            assert_eq!(dashes, $result_h12);
+    /// Returns the encoded symbols of the clock
+    pub fn build(self) -> alloc::vec::IntoIter<Symbol> {
+        let table = match self.encoder {
+            Some(table) => table,
+            None => return self.clock.into_iter().collect::<Vec<_>>().into_iter(),
+        };
 
-            if $hour >= 12 {
-                let dashes: String = Clock::new(hour, minute, Format::Hour24)
-                    .into_iter()
-                    .morse()
-                    .collect();
+        let date = self.clock.date;
+        let values = [
+            date.map(|(month, _)| month.value),
+            date.map(|(_, day)| day.value),
+            Some(self.clock.hour_value()),
+            Some(self.clock.minute.value),
+            self.clock.second.map(|second| second.value),
+        ];
+        let mut symbols = Vec::new();
 
-                assert_eq!(dashes, concat!("----", $result_h12));
+        for value in values.into_iter().flatten() {
+            if !symbols.is_empty() {
+                symbols.push(Symbol::Break(BreakKind::Group));
             }
-        };
+
+            symbols.extend(&table[usize::from(value)]);
+        }
+
+        symbols.into_iter()
     }
+}
 
-    #[test]
-    fn samples() {
-        eq!((00, 00), "-=-");
-        eq!((23, 59), "----..=----..");
-        eq!((12, 00), "-=-");
-        eq!((12, 15), "-=--");
-        eq!((12, 30), "-=---");
-        eq!((12, 34), "-=---");
-        eq!((12, 35), "-=---.");
-        eq!((14, 45), "-..=----");
-        eq!((17, 47), "--..=----");
-        eq!((18, 32), "---=---");
+/// Encodes `value` as its digits in base 5, for [`ClockBuilder::value_encoder`]
+///
+/// The digits are separated by a [`BreakKind::Digit`] break, starting with the most
+/// significant one. A digit from 1 to 4 is that many shorts, a 0 is a single long. A
+/// minute takes at most three digits instead of e.g. the up to twelve symbols of the
+/// minute hand. See [`decode_quinary`] for the inverse.
+///
+/// # Example
+/// ```
+/// # use morseclock::{quinary, MorseExt};
+/// #
+/// assert_eq!(quinary(0).into_iter().morse().collect::<String>(), "-");
+/// assert_eq!(quinary(59).into_iter().morse().collect::<String>(), ".. . ....");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn quinary(value: u8) -> Vec<Symbol> {
+    let mut digits = Vec::new();
+    let mut rest = value;
+
+    loop {
+        digits.push(rest % 5);
+        rest /= 5;
+
+        if rest == 0 {
+            break;
+        }
+    }
+
+    let mut symbols = Vec::new();
+
+    for (i, &digit) in digits.iter().rev().enumerate() {
+        if i > 0 {
+            symbols.push(Symbol::Break(BreakKind::Digit));
+        }
+
+        match digit {
+            0 => symbols.push(Symbol::Long),
+            d => symbols.extend(iter::repeat_n(Symbol::Short, usize::from(d))),
+        }
+    }
+
+    symbols
+}
+
+/// Decodes the value of a single hand encoded by [`quinary`]
+///
+/// Fails with [`Error::DecodeError`] if the symbols aren't encoded by [`quinary`].
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// # use morseclock::{decode_quinary, SymbolSequence};
+/// #
+/// let symbols: SymbolSequence = ".. . ....".parse()?;
+/// assert_eq!(decode_quinary(symbols), Ok(59));
+/// # }
+/// # Ok::<(), morseclock::Error>(())
+/// ```
+pub fn decode_quinary(symbols: impl IntoIterator<Item = Symbol>) -> Result<u8, Error> {
+    let mut value = 0u8;
+    // the digit being decoded and whether it is a single long, a 0
+    let mut digit = None;
+    let mut digits = 0;
+
+    let mut push = |digit: Option<(u8, bool)>| -> Result<(), Error> {
+        let (digit, _) = digit.ok_or(Error::DecodeError)?;
+        let shifted = if digits == 0 {
+            Some(digit)
+        } else if value == 0 {
+            // a leading 0 is only encoded by the value 0 itself
+            None
+        } else {
+            value
+                .checked_mul(5)
+                .and_then(|value| value.checked_add(digit))
+        };
+
+        value = shifted.ok_or(Error::DecodeError)?;
+        digits += 1;
+        Ok(())
+    };
+
+    for symbol in symbols {
+        digit = match (symbol, digit) {
+            (Symbol::Short, None) => Some((1, false)),
+            (Symbol::Short, Some((d, false))) if d < 4 => Some((d + 1, false)),
+            (Symbol::Long, None) => Some((0, true)),
+            (Symbol::Break(BreakKind::Symbol), digit) => digit,
+            (Symbol::Break(BreakKind::Digit), digit) => {
+                push(digit)?;
+                None
+            }
+            _ => return Err(Error::DecodeError),
+        };
+    }
+
+    push(digit)?;
+
+    Ok(value)
+}
+
+/// Returns an iterator over every time displayable in `format`
+///
+/// Yields all 720 hour and minute combinations of a 12 hour clock or all 1440 of a
+/// 24 hour clock, in chronological order.
+///
+/// # Example
+/// ```
+/// # use morseclock::{all_times, Format};
+/// #
+/// assert_eq!(all_times(Format::Hour12).count(), 720);
+/// ```
+pub fn all_times(format: Format) -> impl Iterator<Item = Clock> {
+    let hours = match format {
+        Format::Hour12 => 12,
+        Format::Hour24 => Hour::MAX,
+    };
+
+    (0..hours).flat_map(move |hour| {
+        (0..Minute::MAX).map(move |minute| {
+            Clock::new(
+                Hand {
+                    value: hour,
+                    _marker: PhantomData,
+                },
+                Hand {
+                    value: minute,
+                    _marker: PhantomData,
+                },
+                format,
+            )
+        })
+    })
+}
+
+/// Decodes the hour and minute from the symbols of a [`Clock`]
+///
+/// This is the inverse of the [`Clock`] symbol stream: the minute is recovered at the
+/// granularity of the minute hand, the hour in the range of `format`. A trailing
+/// [`Symbol::Meridiem`] indicator turns the hour on the 12 hour clock back into the
+/// hour of the day. Trailing breaks are ignored, but the hands have to be separated by
+/// exactly one [`BreakKind::Group`] break.
+///
+/// Fails with [`Error::DecodeError`] if the symbols don't describe a time.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// # use morseclock::{decode, Format, SymbolSequence};
+/// #
+/// let symbols: SymbolSequence = "--.=----=".parse()?;
+/// assert_eq!(decode(symbols, Format::Hour12), Ok((4, 45)));
+///
+/// let symbols: SymbolSequence = "--.----".parse()?;
+/// assert!(decode(symbols, Format::Hour12).is_err());
+/// # }
+/// # Ok::<(), morseclock::Error>(())
+/// ```
+pub fn decode(
+    symbols: impl IntoIterator<Item = Symbol>,
+    format: Format,
+) -> Result<(u8, u8), Error> {
+    // the number of long and short symbols of the hour and the minute hand
+    let mut hands = [(0u8, 0u8); 2];
+    let mut hand = 0;
+    let mut meridiem = None;
+    let mut ended = false;
+
+    for symbol in symbols {
+        let (long, short) = &mut hands[hand];
+
+        match symbol {
+            Symbol::Short | Symbol::Long if ended => return Err(Error::DecodeError),
+            Symbol::Long if *short > 0 => return Err(Error::DecodeError),
+            Symbol::Long => *long = long.checked_add(1).ok_or(Error::DecodeError)?,
+            Symbol::Short if *long == 0 => return Err(Error::DecodeError),
+            Symbol::Short => *short = short.checked_add(1).ok_or(Error::DecodeError)?,
+            Symbol::Break(BreakKind::Symbol) => {}
+            Symbol::Break(_) if hand == 1 && *long > 0 => ended = true,
+            Symbol::Break(BreakKind::Group) if hand == 0 && *long > 0 => hand = 1,
+            Symbol::Meridiem(m)
+                if hand == 1 && *long > 0 && meridiem.is_none() && format == Format::Hour12 =>
+            {
+                meridiem = Some(m);
+                ended = true;
+            }
+            Symbol::Break(_) | Symbol::Meridiem(_) => return Err(Error::DecodeError),
+        }
+    }
+
+    let [(hour_long, hour_short), (minute_long, minute_short)] = hands;
+    let hour = Hour::from_long_short(hour_long, hour_short).ok_or(Error::DecodeError)?;
+    let minute = Minute::from_long_short(minute_long, minute_short).ok_or(Error::DecodeError)?;
+
+    let hour = match (format, meridiem) {
+        (Format::Hour12, _) if hour >= 12 => return Err(Error::DecodeError),
+        (Format::Hour12, Some(Meridiem::Pm)) => hour + 12,
+        (Format::Hour12, _) => hour,
+        (Format::Hour24, _) => hour % Hour::MAX,
+    };
+
+    Ok((hour, minute))
+}
+
+impl IntoIterator for Clock {
+    type Item = Symbol;
+    type IntoIter = ClockIter;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        let meridiem = match self.hour.value {
+            0..=11 => Meridiem::Am,
+            _ => Meridiem::Pm,
+        };
+
+        self.hour.value = self.hour_value();
+
+        let mut iter = ClockIter::default();
+
+        if let Some((month, day)) = self.date {
+            iter.extend(month);
+            iter.push(Symbol::Break(BreakKind::Group));
+            iter.extend(day);
+            iter.push(Symbol::Break(BreakKind::Group));
+        }
+
+        iter.extend(self.hour);
+        iter.push(Symbol::Break(BreakKind::Group));
+        iter.extend(self.minute);
+
+        if let Some(second) = self.second {
+            iter.push(Symbol::Break(BreakKind::Group));
+            iter.extend(second);
+        }
+
+        if self.meridiem && self.format == Format::Hour12 {
+            iter.push(Symbol::Meridiem(meridiem));
+        }
+
+        iter
+    }
+}
+
+/// An iterator over a [`Clock`] which produces a series of [`Symbol`]s
+///
+/// A clone continues at the position of the original.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClockIter {
+    symbols: [Symbol; ClockIter::CAPACITY],
+    len: usize,
+    pos: usize,
+}
+
+impl ClockIter {
+    /// The maximum number of symbols a [`Clock`] produces
+    const CAPACITY: usize = 48;
+
+    fn push(&mut self, symbol: Symbol) {
+        self.symbols[self.len] = symbol;
+        self.len += 1;
+    }
+
+    fn extend(&mut self, symbols: impl IntoIterator<Item = Symbol>) {
+        for symbol in symbols {
+            self.push(symbol);
+        }
+    }
+}
+
+impl Default for ClockIter {
+    fn default() -> Self {
+        Self {
+            symbols: [Symbol::Break(BreakKind::Group); Self::CAPACITY],
+            len: 0,
+            pos: 0,
+        }
+    }
+}
+
+impl Iterator for ClockIter {
+    type Item = Symbol;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let symbol = self.symbols[self.pos..self.len].first().copied();
+
+        if symbol.is_some() {
+            self.pos += 1;
+        }
+
+        symbol
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.pos;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for ClockIter {}
+
+/// An extension trait for iterators which yield [`Symbol`]s
+pub trait MorseExt {
+    type Output;
+    fn morse(self) -> Self::Output;
+
+    /// Renders the symbols with the strings of `glyphs`, e.g. as syllables
+    ///
+    /// # Example
+    /// ```
+    /// # use morseclock::{Clock, Format, MorseExt, MorseGlyphs};
+    /// #
+    /// let clock = Clock::new(16.try_into()?, 47.try_into()?, Format::Hour12);
+    /// let glyphs = MorseGlyphs {
+    ///     short: "▪",
+    ///     long: "▬",
+    ///     group_break: " ",
+    ///     ..MorseGlyphs::default()
+    /// };
+    ///
+    /// assert_eq!(clock.into_iter().morse_with(glyphs).collect::<String>(), "▬▬▪ ▬▬▬▬");
+    /// # Ok::<(), morseclock::Error>(())
+    /// ```
+    fn morse_with(self, glyphs: MorseGlyphs<'_>) -> MorseWith<'_, Self>
+    where
+        Self: Sized;
+
+    /// Renders the symbols as text in the spacing common for CW practice
+    ///
+    /// The dots and dashes of a character are adjacent, characters are separated by a
+    /// space and words (e.g. the hands of a [`Clock`]) by `" / "`. Consecutive breaks
+    /// are rendered as the longest one, breaks at the start and end not at all.
+    ///
+    /// # Example
+    /// ```
+    /// # use morseclock::{Clock, Format, MorseExt};
+    /// #
+    /// let clock = Clock::new(16.try_into()?, 47.try_into()?, Format::Hour12);
+    ///
+    /// assert_eq!(clock.into_iter().cw_text(), "--. / ----");
+    /// # Ok::<(), morseclock::Error>(())
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn cw_text(self) -> String;
+
+    /// Renders the symbols as Unicode Braille patterns, see [`MorseGlyphs::BRAILLE`]
+    ///
+    /// # Example
+    /// ```
+    /// # use morseclock::{Clock, Format, MorseExt};
+    /// #
+    /// let clock = Clock::new(16.try_into()?, 47.try_into()?, Format::Hour12);
+    ///
+    /// assert_eq!(clock.into_iter().braille(), "⠉⠉⠁⠀⠉⠉⠉⠉");
+    /// # Ok::<(), morseclock::Error>(())
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn braille(self) -> String;
+
+    /// Yields the glyph of every symbol with the durations it is on and off
+    ///
+    /// The durations are the ones of [`TimingParams::durations`]. The glyphs are the ones
+    /// of [`MorseExt::morse`], so a break within a character is yielded with an empty
+    /// glyph.
+    ///
+    /// Fails with [`Error::InvalidTiming`] when a duty cycle isn't in the range [0, 1] or
+    /// `break_factor` is negative.
+    ///
+    /// # Example
+    /// ```
+    /// # use core::time::Duration;
+    /// # use morseclock::{Clock, Format, MorseExt};
+    /// #
+    /// let clock = Clock::new(1.try_into()?, 0.try_into()?, Format::Hour12);
+    /// let ms = Duration::from_millis;
+    /// let timed: Vec<_> = clock.into_iter().morse_timed(ms(100), 0.2, 0.6, 2.0)?.collect();
+    ///
+    /// assert_eq!(
+    ///     timed,
+    ///     [
+    ///         ("-", ms(60), ms(40)),
+    ///         (".", ms(20), ms(80)),
+    ///         ("=", ms(0), ms(200)),
+    ///         ("-", ms(60), ms(40)),
+    ///     ]
+    /// );
+    /// # Ok::<(), morseclock::Error>(())
+    /// ```
+    fn morse_timed(
+        self,
+        base: Duration,
+        short_duty: f64,
+        long_duty: f64,
+        break_factor: f64,
+    ) -> Result<MorseTimed<Self>, Error>
+    where
+        Self: Sized;
+}
+
+/// An iterator adapter which produces a series of morsecode-like symbols
+///
+/// A [`BreakKind::Group`] break is rendered as `'='` and a [`BreakKind::Digit`] break
+/// as `' '`. Breaks within a character aren't rendered at all. A [`Symbol::Meridiem`]
+/// is rendered as `'A'` or `'P'`. These are the glyphs of [`MorseGlyphs::default`].
+///
+/// See [`MorseExt`]
+pub struct Morse<I>(MorseWith<'static, I>);
+
+/// The strings rendered for each [`Symbol`] by [`MorseExt::morse_with`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MorseGlyphs<'a> {
+    pub short: &'a str,
+    pub long: &'a str,
+    /// The gap between two symbols of a character, usually implied by adjacent glyphs
+    pub symbol_break: &'a str,
+    pub digit_break: &'a str,
+    pub group_break: &'a str,
+    pub am: &'a str,
+    pub pm: &'a str,
+}
+
+impl MorseGlyphs<'static> {
+    /// The glyphs rendered by [`MorseExt::morse`]
+    pub const DEFAULT: Self = Self {
+        short: ".",
+        long: "-",
+        symbol_break: "",
+        digit_break: " ",
+        group_break: "=",
+        am: "A",
+        pm: "P",
+    };
+
+    /// Braille cells of the Unicode block starting at U+2800, e.g. for a Braille display
+    ///
+    /// A short is the single dot 1 (`⠁`) and a long the dots 1 and 4 side by side (`⠉`).
+    /// Digit and group breaks are a blank cell (`U+2800`), the meridiem isn't rendered.
+    pub const BRAILLE: Self = Self {
+        short: "\u{2801}",
+        long: "\u{2809}",
+        symbol_break: "",
+        digit_break: "\u{2800}",
+        group_break: "\u{2800}",
+        am: "",
+        pm: "",
+    };
+}
+
+impl<'a> MorseGlyphs<'a> {
+    /// The string rendered for `symbol`
+    fn glyph(&self, symbol: Symbol) -> &'a str {
+        match symbol {
+            Symbol::Break(BreakKind::Symbol) => self.symbol_break,
+            Symbol::Break(BreakKind::Digit) => self.digit_break,
+            Symbol::Break(BreakKind::Group) => self.group_break,
+            Symbol::Short => self.short,
+            Symbol::Long => self.long,
+            Symbol::Meridiem(Meridiem::Am) => self.am,
+            Symbol::Meridiem(Meridiem::Pm) => self.pm,
+        }
+    }
+}
+
+impl Default for MorseGlyphs<'static> {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// An iterator adapter which renders symbols with [`MorseGlyphs`]
+///
+/// Empty glyphs are skipped. See [`MorseExt::morse_with`]
+pub struct MorseWith<'a, I> {
+    symbols: I,
+    glyphs: MorseGlyphs<'a>,
+}
+
+/// An iterator adapter which yields the glyph of a symbol with its on and off durations
+///
+/// See [`MorseExt::morse_timed`]
+pub struct MorseTimed<I> {
+    symbols: I,
+    params: TimingParams,
+}
+
+impl<I> MorseExt for I
+where
+    I: Iterator<Item = Symbol>,
+{
+    type Output = Morse<I>;
+
+    fn morse(self) -> Self::Output {
+        Morse(self.morse_with(MorseGlyphs::DEFAULT))
+    }
+
+    fn morse_with(self, glyphs: MorseGlyphs<'_>) -> MorseWith<'_, Self> {
+        MorseWith {
+            symbols: self,
+            glyphs,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn cw_text(self) -> String {
+        let mut text = String::new();
+        let mut gap = None;
+
+        for symbol in self {
+            let glyph = match symbol {
+                Symbol::Break(kind) => {
+                    gap = gap.max(Some(kind));
+                    continue;
+                }
+                Symbol::Short => '.',
+                Symbol::Long => '-',
+                // there's no morse code for it
+                Symbol::Meridiem(_) => continue,
+            };
+
+            if !text.is_empty() {
+                match gap {
+                    Some(BreakKind::Group) => text.push_str(" / "),
+                    Some(BreakKind::Digit) => text.push(' '),
+                    Some(BreakKind::Symbol) | None => {}
+                }
+            }
+
+            gap = None;
+            text.push(glyph);
+        }
+
+        text
+    }
+
+    #[cfg(feature = "alloc")]
+    fn braille(self) -> String {
+        self.morse_with(MorseGlyphs::BRAILLE).collect()
+    }
+
+    fn morse_timed(
+        self,
+        base: Duration,
+        short_duty: f64,
+        long_duty: f64,
+        break_factor: f64,
+    ) -> Result<MorseTimed<Self>, Error> {
+        Ok(MorseTimed {
+            symbols: self,
+            params: TimingParams::new(base, short_duty, long_duty, break_factor)?,
+        })
+    }
+}
+
+impl<I> Iterator for Morse<I>
+where
+    I: Iterator<Item = Symbol>,
+{
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // every default glyph is a single character
+        self.0.next().and_then(|glyph| glyph.chars().next())
+    }
+}
+
+impl<'a, I> Iterator for MorseWith<'a, I>
+where
+    I: Iterator<Item = Symbol>,
+{
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let glyph = self.glyphs.glyph(self.symbols.next()?);
+
+            if !glyph.is_empty() {
+                return Some(glyph);
+            }
+        }
+    }
+}
+
+impl<I> Iterator for MorseTimed<I>
+where
+    I: Iterator<Item = Symbol>,
+{
+    type Item = (&'static str, Duration, Duration);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let symbol = self.symbols.next()?;
+        let (on, off) = self.params.durations(symbol);
+
+        Some((MorseGlyphs::DEFAULT.glyph(symbol), on, off))
+    }
+}
+
+/// The state a LED is switched to by a [`schedule`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LedState {
+    On,
+    Off,
+}
+
+/// The durations a [`schedule`] is made of, see [`MorseExt::morse_timed`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimingParams {
+    base: Duration,
+    short_duty: f64,
+    long_duty: f64,
+    break_factor: f64,
+    group_break: Duration,
+}
+
+impl TimingParams {
+    /// Creates the durations of blinks lasting `base`, on for `short_duty` and
+    /// `long_duty` of it, and of a break between two hands of `base` times `break_factor`
+    ///
+    /// Fails with [`Error::InvalidTiming`] when a duty cycle isn't in the range [0, 1] or
+    /// `break_factor` is negative.
+    pub fn new(
+        base: Duration,
+        short_duty: f64,
+        long_duty: f64,
+        break_factor: f64,
+    ) -> Result<Self, Error> {
+        let duty = 0.0..=1.0;
+        if !duty.contains(&short_duty) || !duty.contains(&long_duty) {
+            return Err(Error::InvalidTiming);
+        }
+
+        let group_break = if duty.contains(&break_factor) {
+            fraction(base, break_factor)
+        } else {
+            // also rejects a negative or NaN factor and a break overflowing a duration
+            Duration::try_from_secs_f64(base.as_secs_f64() * break_factor)
+                .map_err(|_| Error::InvalidTiming)?
+        };
+
+        Ok(TimingParams {
+            base,
+            short_duty,
+            long_duty,
+            break_factor,
+            group_break,
+        })
+    }
+
+    /// Duration of a short or long blink
+    pub fn base(&self) -> Duration {
+        self.base
+    }
+
+    /// The fraction of `base` a short blink is on for
+    pub fn short_duty(&self) -> f64 {
+        self.short_duty
+    }
+
+    /// The fraction of `base` a long blink is on for
+    pub fn long_duty(&self) -> f64 {
+        self.long_duty
+    }
+
+    /// The duration of a break between two hands relative to `base`
+    pub fn break_factor(&self) -> f64 {
+        self.break_factor
+    }
+
+    /// Returns the on and off durations of `symbol`
+    ///
+    /// A short and a long last `base` each, of which they are on for `short_duty` and
+    /// `long_duty`. A [`BreakKind::Group`] break is off for `base` times `break_factor`.
+    /// The shorter breaks keep the proportions of morse code, 3/7 of it between digits
+    /// and 1/7 within a character. A [`Symbol::Meridiem`] doesn't last at all.
+    pub fn durations(&self, symbol: Symbol) -> (Duration, Duration) {
+        let blink = |duty: f64| {
+            let on = fraction(self.base, duty);
+            (on, self.base - on)
+        };
+        let group_break = self.group_break;
+
+        match symbol {
+            Symbol::Break(BreakKind::Symbol) => (Duration::ZERO, group_break / 7),
+            // can't overflow like multiplying by 3 first
+            Symbol::Break(BreakKind::Digit) => (Duration::ZERO, fraction(group_break, 3.0 / 7.0)),
+            Symbol::Break(BreakKind::Group) => (Duration::ZERO, group_break),
+            Symbol::Short => blink(self.short_duty),
+            Symbol::Long => blink(self.long_duty),
+            Symbol::Meridiem(_) => (Duration::ZERO, Duration::ZERO),
+        }
+    }
+}
+
+/// `duration` times `fraction` in the range [0, 1], rounded to nanoseconds
+///
+/// Unlike [`Duration::mul_f64`], this never exceeds `duration`, so a blink is never on
+/// for longer than it lasts.
+fn fraction(duration: Duration, fraction: f64) -> Duration {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+    let nanos = duration.as_nanos();
+    // adding 0.5 rounds without `f64::round`, which needs `std`; the conversion
+    // truncates and saturates, e.g. turning NaN into 0
+    let product = ((nanos as f64 * fraction + 0.5) as u128).min(nanos);
+
+    Duration::new(
+        (product / NANOS_PER_SEC) as u64,
+        (product % NANOS_PER_SEC) as u32,
+    )
+}
+
+/// The states a LED displaying `clock` is switched to, each with its duration
+///
+/// Consecutive durations of the same state are merged, e.g. the off time of a blink and
+/// the following break. The schedule starts with the first blink and ends after the off
+/// time of the last one, the pause between two times isn't part of it.
+///
+/// # Example
+/// ```
+/// # use core::time::Duration;
+/// # use morseclock::{schedule, Clock, Format, LedState, TimingParams};
+/// #
+/// let clock = Clock::new(3.try_into()?, 0.try_into()?, Format::Hour12);
+/// let params = TimingParams::new(Duration::from_millis(100), 0.2, 0.5, 1.0)?;
+///
+/// // "--=-"
+/// assert_eq!(schedule(clock, &params).len(), 6);
+/// assert_eq!(schedule(clock, &params)[3], (LedState::Off, Duration::from_millis(150)));
+/// # Ok::<(), morseclock::Error>(())
+/// ```
+#[cfg(feature = "alloc")]
+pub fn schedule(clock: Clock, params: &TimingParams) -> Vec<(LedState, Duration)> {
+    schedule_with(clock, |symbol| params.durations(symbol))
+}
+
+/// Like [`schedule`], but for any `symbols`, which are on and off for the durations
+/// returned by `durations`
+///
+/// Merged durations saturate at [`Duration::MAX`].
+///
+/// # Example
+/// ```
+/// # use core::time::Duration;
+/// # use morseclock::{schedule_with, LedState, Symbol};
+/// #
+/// let ms = Duration::from_millis;
+/// let symbols = [Symbol::Short, Symbol::Short];
+///
+/// assert_eq!(
+///     schedule_with(symbols, |_| (ms(10), ms(20))),
+///     [
+///         (LedState::On, ms(10)),
+///         (LedState::Off, ms(20)),
+///         (LedState::On, ms(10)),
+///         (LedState::Off, ms(20)),
+///     ]
+/// );
+/// ```
+#[cfg(feature = "alloc")]
+pub fn schedule_with<I, F>(symbols: I, mut durations: F) -> Vec<(LedState, Duration)>
+where
+    I: IntoIterator<Item = Symbol>,
+    F: FnMut(Symbol) -> (Duration, Duration),
+{
+    let mut schedule: Vec<(LedState, Duration)> = Vec::new();
+
+    for symbol in symbols {
+        let (on, off) = durations(symbol);
+
+        for (state, duration) in [(LedState::On, on), (LedState::Off, off)] {
+            match schedule.last_mut() {
+                _ if duration.is_zero() => {}
+                Some((last, total)) if *last == state => *total = total.saturating_add(duration),
+                _ => schedule.push((state, duration)),
+            }
+        }
+    }
+
+    schedule
+}
+
+/// Run-length encodes a series of [`Symbol`]s into `(symbol, count)` pairs
+///
+/// Runs longer than [`u16::MAX`] are split into multiple pairs.
+/// See [`expand`] for the inverse.
+///
+/// # Example
+/// ```
+/// # use morseclock::{rle, Symbol};
+/// #
+/// let symbols = [Symbol::Long, Symbol::Long, Symbol::Short];
+///
+/// assert_eq!(rle(symbols.into_iter()), [(Symbol::Long, 2), (Symbol::Short, 1)]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn rle(symbols: impl Iterator<Item = Symbol>) -> Vec<(Symbol, u16)> {
+    let mut runs: Vec<(Symbol, u16)> = Vec::new();
+
+    for symbol in symbols {
+        match runs.last_mut() {
+            Some((last, count)) if *last == symbol && *count < u16::MAX => *count += 1,
+            _ => runs.push((symbol, 1)),
+        }
+    }
+
+    runs
+}
+
+/// Expands `(symbol, count)` pairs produced by [`rle`] into a series of [`Symbol`]s
+pub fn expand(runs: &[(Symbol, u16)]) -> impl Iterator<Item = Symbol> + '_ {
+    runs.iter()
+        .flat_map(|&(symbol, count)| iter::repeat_n(symbol, count as usize))
+}
+
+/// Counts the positions at which the symbols of `a` and `b` differ
+///
+/// The shorter of both symbol streams is padded, so every additional symbol of the
+/// longer one counts as a difference. Times with a large distance are hard to mistake
+/// for one another.
+///
+/// # Example
+/// ```
+/// # use morseclock::{stream_distance, Clock, Format};
+/// #
+/// let a = Clock::new(4.try_into()?, 45.try_into()?, Format::Hour12);
+/// let b = Clock::new(4.try_into()?, 50.try_into()?, Format::Hour12);
+///
+/// // "--.=----" and "--.=----."
+/// assert_eq!(stream_distance(&a, &b), 1);
+/// # Ok::<(), morseclock::Error>(())
+/// ```
+pub fn stream_distance(a: &Clock, b: &Clock) -> usize {
+    let mut a = a.into_iter().fuse();
+    let mut b = b.into_iter().fuse();
+
+    iter::from_fn(|| match (a.next(), b.next()) {
+        (None, None) => None,
+        (a, b) => Some(a != b),
+    })
+    .filter(|&differs| differs)
+    .count()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use alloc::{borrow::ToOwned, format, string::ToString, vec};
+
+    #[cfg(feature = "alloc")]
+    macro_rules! eq {
+        (($hour:expr, $minute:expr), $result_h12:expr) => {
+            let hour = $hour.try_into().unwrap();
+            let minute = $minute.try_into().unwrap();
+
+            let dashes: String = Clock::new(hour, minute, Format::Hour12)
+                .into_iter()
+                .morse()
+                .collect();
+
+            assert_eq!(dashes, $result_h12);
+
+            if $hour >= 12 {
+                let dashes: String = Clock::new(hour, minute, Format::Hour24)
+                    .into_iter()
+                    .morse()
+                    .collect();
+
+                assert_eq!(dashes, concat!("----", $result_h12));
+            }
+        };
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn samples() {
+        eq!((00, 00), "-=-");
+        eq!((23, 59), "----..=----..");
+        eq!((12, 00), "-=-");
+        eq!((12, 15), "-=--");
+        eq!((12, 30), "-=---");
+        eq!((12, 34), "-=---");
+        eq!((12, 35), "-=---.");
+        eq!((14, 45), "-..=----");
+        eq!((17, 47), "--..=----");
+        eq!((18, 32), "---=---");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn hour24() {
+        let morse = |hour: u32, minute: u32| -> String {
+            Clock::new(
+                hour.try_into().unwrap(),
+                minute.try_into().unwrap(),
+                Format::Hour24,
+            )
+            .into_iter()
+            .morse()
+            .collect()
+        };
+
+        assert_eq!(morse(0, 0), "-=-");
+        assert_eq!(morse(12, 0), "-----=-");
+        assert_eq!(morse(13, 45), "-----.=----");
+        assert_eq!(morse(23, 59), "--------..=----..");
+    }
+
+    #[test]
+    fn zero_starts_with_long() {
+        for value in 0..24 {
+            let hour = Hand::<Hour>::try_from(value).unwrap();
+            assert_eq!(hour.into_iter().next(), Some(Symbol::Long));
+        }
+
+        for value in 0..60 {
+            let minute = Hand::<Minute>::try_from(value).unwrap();
+            assert_eq!(minute.into_iter().next(), Some(Symbol::Long));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn preamble() {
+        let hour = 16.try_into().unwrap();
+        let minute = 47.try_into().unwrap();
+        let clock = Clock::new(hour, minute, Format::Hour12);
+
+        // a noisy prefix which contains parts of the preamble
+        let mut symbols = vec![
+            Symbol::Short,
+            Symbol::Long,
+            Symbol::Short,
+            Symbol::Long,
+            Symbol::Short,
+            Symbol::Long,
+            Symbol::Short,
+        ];
+        symbols.extend(PREAMBLE);
+        symbols.extend(clock);
+
+        let start = find_preamble(&symbols).unwrap();
+        let time: String = symbols[start..].iter().copied().morse().collect();
+
+        assert_eq!(start, 13);
+        assert_eq!(time, "--.=----");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn eot() {
+        let clock = Clock::new(
+            23.try_into().unwrap(),
+            59.try_into().unwrap(),
+            Format::Hour12,
+        );
+
+        let mut symbols: Vec<_> = clock.into_iter().collect();
+        symbols.extend(EOT);
+        symbols.extend(PREAMBLE);
+        symbols.extend(EOT);
+
+        let end = find_eot(&symbols).unwrap();
+        let time: String = symbols[..end].iter().copied().morse().collect();
+
+        assert_eq!(end, 13);
+        assert_eq!(time, "----..=----..");
+        assert_eq!(find_eot(&symbols[..end]), None);
+        assert_eq!(find_eot(&EOT[1..]), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn no_preamble() {
+        let hour = 16.try_into().unwrap();
+        let minute = 47.try_into().unwrap();
+        let symbols: Vec<_> = Clock::new(hour, minute, Format::Hour12)
+            .into_iter()
+            .collect();
+
+        assert_eq!(find_preamble(&symbols), None);
+        assert_eq!(find_preamble(&PREAMBLE[1..]), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn seconds() {
+        let clock = |h: u32, m: u32, s: u32| -> String {
+            Clock::with_seconds(
+                h.try_into().unwrap(),
+                m.try_into().unwrap(),
+                s.try_into().unwrap(),
+                Format::Hour24,
+            )
+            .into_iter()
+            .morse()
+            .collect()
+        };
+
+        assert_eq!(clock(9, 41, 7), "----=---..=-.");
+        assert_eq!(clock(23, 59, 59), "--------..=----..=----..");
+        assert_eq!(clock(0, 0, 0), "-=-=-");
+        assert!(Hand::<Second>::try_from(60).is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn seconds_symbols() {
+        use Symbol::{Long, Short};
+        let group = Symbol::Break(BreakKind::Group);
+
+        let hour = 12.try_into().unwrap();
+        let minute = 34.try_into().unwrap();
+        let clock = Clock::with_seconds(hour, minute, 56.try_into().unwrap(), Format::Hour12);
+
+        assert_eq!(
+            clock.into_iter().collect::<Vec<_>>(),
+            [Long, group, Long, Long, Long, group, Long, Long, Long, Long, Short, Short]
+        );
+        // without seconds the time keeps its two groups
+        assert_eq!(
+            Clock::new(hour, minute, Format::Hour12)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [Long, group, Long, Long, Long]
+        );
+        assert_eq!(Hand::<Second>::try_from(60), Err(Error::InvalidHandValue));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn date() {
+        use Symbol::{Long, Short};
+        let group = Symbol::Break(BreakKind::Group);
+
+        // 2024-07-04 12:00
+        let clock = Clock::with_date(
+            7.try_into().unwrap(),
+            4.try_into().unwrap(),
+            12.try_into().unwrap(),
+            0.try_into().unwrap(),
+            Format::Hour24,
+        );
+
+        assert_eq!(
+            clock.symbols(),
+            [
+                Long, Long, Long, Short, group, Long, Long, Short, group, Long, Long, Long, Long,
+                Long, group, Long
+            ]
+        );
+
+        // the longest date and time still fits
+        let clock = Clock {
+            second: Some(59.try_into().unwrap()),
+            ..Clock::with_date(
+                11.try_into().unwrap(),
+                29.try_into().unwrap(),
+                23.try_into().unwrap(),
+                59.try_into().unwrap(),
+                Format::Hour24,
+            )
+        };
+        assert_eq!(
+            clock.into_iter().count(),
+            6 + 1 + 12 + 1 + 10 + 1 + 6 + 1 + 6
+        );
+    }
+
+    #[test]
+    fn invalid_date() {
+        assert_eq!(Hand::<Month>::try_from(13), Err(Error::InvalidHandValue));
+        assert_eq!(Hand::<Month>::try_from(0), Err(Error::InvalidHandValue));
+        assert_eq!(Hand::<Day>::try_from(32), Err(Error::InvalidHandValue));
+        assert_eq!(Hand::<Day>::try_from(0), Err(Error::InvalidHandValue));
+        assert!(Hand::<Month>::try_from(12).is_ok());
+        assert!(Hand::<Day>::try_from(31).is_ok());
+
+        for month in 1..13 {
+            let (long, short) = Month::to_long_short(month);
+            assert_eq!(Month::from_long_short(long, short), Some(month));
+        }
+        for day in 1..32 {
+            let (long, short) = Day::to_long_short(day);
+            assert_eq!(Day::from_long_short(long, short), Some(day));
+        }
+        assert_eq!(Month::from_long_short(1, 0), None);
+        assert_eq!(Day::from_long_short(11, 2), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn builder() {
+        use Symbol::{Long, Short};
+
+        for (hour, minute) in [(0, 0), (16, 47), (23, 59)] {
+            for format in [Format::Hour12, Format::Hour24] {
+                let hour = hour.try_into().unwrap();
+                let minute = minute.try_into().unwrap();
+
+                assert!(Clock::builder(hour, minute, format)
+                    .build()
+                    .eq(Clock::new(hour, minute, format)));
+            }
+        }
+
+        let cut: [&[Symbol]; 10] = [
+            &[Long],
+            &[Short, Long],
+            &[Short, Short, Long],
+            &[Short, Short, Short, Long],
+            &[Short, Short, Short, Short, Long],
+            &[Short],
+            &[Long, Short, Short, Short, Short],
+            &[Long, Short, Short, Short],
+            &[Long, Short, Short],
+            &[Long, Short],
+        ];
+        let time: String = Clock::builder(
+            16.try_into().unwrap(),
+            5.try_into().unwrap(),
+            Format::Hour24,
+        )
+        .second(38.try_into().unwrap())
+        .digits(&cut)
+        .unwrap()
+        .build()
+        .morse()
+        .collect();
+
+        assert_eq!(time, ".- -....=- .=...- -..");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn from_chrono() {
+        use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+
+        let time = NaiveTime::from_hms_opt(21, 40, 59).unwrap();
+        let clock = Clock::new(
+            21.try_into().unwrap(),
+            40.try_into().unwrap(),
+            Format::Hour12,
+        );
+
+        assert_eq!(Clock::try_from(time), Ok(clock));
+        assert!(Clock::try_from(time)
+            .unwrap()
+            .into_iter()
+            .eq(clock.into_iter()));
+        assert!(Clock::try_from(time)
+            .unwrap()
+            .into_iter()
+            .morse()
+            .eq("----=---..".chars()));
+
+        let date_time =
+            Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(2024, 7, 4).unwrap().and_time(time));
+        assert_eq!(Clock::try_from(date_time), Ok(clock));
+        assert_eq!(
+            Clock::from_time(&date_time.naive_utc(), Format::Hour24),
+            Ok(Clock {
+                format: Format::Hour24,
+                ..clock
+            })
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        fn clock(hour: u64, minute: u64) -> Result<Clock, Error> {
+            let hour = u32::try_from(hour)?;
+            let minute = u32::try_from(minute)?;
+
+            Ok(Clock::new(
+                hour.try_into()?,
+                minute.try_into()?,
+                Format::Hour24,
+            ))
+        }
+
+        assert!(clock(23, 59).is_ok());
+        assert_eq!(clock(24, 0), Err(Error::InvalidHandValue));
+        assert_eq!(clock(0, 60), Err(Error::InvalidHandValue));
+        assert_eq!(clock(u64::MAX, 0), Err(Error::OutOfRange));
+        assert_eq!(clock(0, 1 << 32), Err(Error::OutOfRange));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn display_hour() {
+        let hour12: Vec<_> = (0..24)
+            .map(|hour| to_display_hour(hour, Format::Hour12))
+            .collect();
+
+        assert_eq!(
+            hour12,
+            [12, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]
+        );
+        assert_eq!(to_display_hour(0, Format::Hour12), 12);
+        assert_eq!(to_display_hour(12, Format::Hour12), 12);
+        assert_eq!(to_display_hour(13, Format::Hour12), 1);
+        assert_eq!(to_display_hour(23, Format::Hour12), 11);
+
+        for hour in 0..24 {
+            assert_eq!(to_display_hour(hour, Format::Hour24), hour);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn twelve_o_clock() {
+        let hour = |hour: u32| {
+            let clock = Clock::new(
+                hour.try_into().unwrap(),
+                0.try_into().unwrap(),
+                Format::Hour12,
+            );
+            clock.into_iter().morse().collect::<String>()
+        };
+
+        // 12 o'clock is the upright position of the hand, both at midnight and noon
+        assert_eq!(hour(0), "-=-");
+        assert_eq!(hour(12), "-=-");
+        assert_eq!(hour(13), "-.=-");
+        assert_eq!(hour(23), "----..=-");
+        for h in 0..12 {
+            assert_eq!(hour(h), hour(h + 12));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn quinary_encoding() {
+        // the digit encoding of quinary, but with up to 9 shorts
+        let decimal = |digit| match digit {
+            0 => vec![Symbol::Long],
+            d => vec![Symbol::Short; usize::from(d)],
+        };
+        let minute = |minute: u32, quinary: bool| {
+            let builder = Clock::builder(
+                0.try_into().unwrap(),
+                minute.try_into().unwrap(),
+                Format::Hour24,
+            );
+            let builder = if quinary {
+                builder.value_encoder(super::quinary)
+            } else {
+                builder.digit_encoder(decimal)
+            };
+            let symbols: String = builder.unwrap().build().morse().collect();
+
+            // without the hour
+            symbols.split_once('=').unwrap().1.to_owned()
+        };
+
+        assert_eq!(minute(0, false), "- -");
+        assert_eq!(minute(0, true), "-");
+        assert_eq!(minute(24, false), ".. ....");
+        assert_eq!(minute(24, true), ".... ....");
+        assert_eq!(minute(59, false), "..... .........");
+        assert_eq!(minute(59, true), ".. . ....");
+
+        for value in 0..Minute::MAX {
+            assert_eq!(decode_quinary(quinary(value)), Ok(value));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn invalid_quinary() {
+        for symbols in [
+            "",
+            " ",
+            "-.",
+            ".... .....",
+            "- .",
+            ".. -=",
+            "..  .",
+            "-.-. . . -",
+        ] {
+            let symbols: SymbolSequence = symbols.parse().unwrap();
+            assert_eq!(
+                decode_quinary(symbols.clone()),
+                Err(Error::DecodeError),
+                "{:?}",
+                symbols
+            );
+        }
+
+        // values up to 255 fit into a u8, 2022 in base 5 is 262
+        assert_eq!(decode_quinary(quinary(255)), Ok(255));
+        let symbols: SymbolSequence = ". . - .".parse().unwrap();
+        assert_eq!(decode_quinary(symbols), Ok(151));
+        let symbols: SymbolSequence = ".. - .. ..".parse().unwrap();
+        assert_eq!(decode_quinary(symbols), Err(Error::DecodeError));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn invalid_encoding() {
+        let builder =
+            || Clock::builder(1.try_into().unwrap(), 2.try_into().unwrap(), Format::Hour12);
+        let table = vec![vec![Symbol::Short]; 10];
+
+        assert!(builder().digits(&table).is_ok());
+        // digit 9 is missing
+        assert_eq!(
+            builder().digits(&table[..9]).err(),
+            Some(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            builder()
+                .digit_encoder(|d| if d == 3 { vec![] } else { vec![Symbol::Long] })
+                .err(),
+            Some(Error::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn exact_size() {
+        let clocks = [
+            Clock::new(0.try_into().unwrap(), 0.try_into().unwrap(), Format::Hour24),
+            Clock::new(
+                23.try_into().unwrap(),
+                59.try_into().unwrap(),
+                Format::Hour24,
+            ),
+            Clock {
+                meridiem: true,
+                ..Clock::new(
+                    14.try_into().unwrap(),
+                    0.try_into().unwrap(),
+                    Format::Hour12,
+                )
+            },
+            Clock::with_seconds(
+                12.try_into().unwrap(),
+                34.try_into().unwrap(),
+                56.try_into().unwrap(),
+                Format::Hour12,
+            ),
+        ];
+
+        for clock in clocks {
+            let mut iter = clock.into_iter();
+            let len = iter.len();
+
+            assert_eq!(len, clock.into_iter().count());
+            iter.next();
+            assert_eq!(iter.size_hint(), (len - 1, Some(len - 1)));
+            assert_eq!(iter.by_ref().count(), len - 1);
+            assert_eq!(iter.len(), 0);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn clone_partially_consumed() {
+        let clock = Clock::new(
+            16.try_into().unwrap(),
+            47.try_into().unwrap(),
+            Format::Hour24,
+        );
+        let mut iter = clock.into_iter();
+        iter.nth(2);
+
+        let mut clone = iter.clone();
+        assert_eq!(clone, iter);
+        assert_eq!(clone.len(), iter.len());
+
+        assert_eq!(clone.next(), iter.next());
+        assert_eq!(clone, iter);
+
+        let mut fresh = clock.into_iter();
+        fresh.nth(3);
+        assert_eq!(fresh, iter);
+        assert_ne!(clock.into_iter(), iter);
+
+        assert_eq!(clone.collect::<Vec<_>>(), iter.collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn symbols() {
+        for format in [Format::Hour12, Format::Hour24] {
+            for clock in all_times(format) {
+                assert_eq!(clock.symbols(), clock.into_iter().collect::<Vec<_>>());
+            }
+        }
+
+        let clock = Clock::with_seconds(
+            12.try_into().unwrap(),
+            34.try_into().unwrap(),
+            56.try_into().unwrap(),
+            Format::Hour12,
+        );
+        assert_eq!(clock.symbols(), clock.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn decode_all_times() {
+        for format in [Format::Hour12, Format::Hour24] {
+            for clock in all_times(format) {
+                let minute = clock.minute.value / 5 * 5;
+                let hour = match format {
+                    Format::Hour12 => clock.hour.value % 12,
+                    Format::Hour24 => clock.hour.value,
+                };
+
+                assert_eq!(decode(clock, format), Ok((hour, minute)));
+            }
+        }
+
+        for clock in all_times(Format::Hour24) {
+            let clock = Clock {
+                format: Format::Hour12,
+                meridiem: true,
+                ..clock
+            };
+            let time = (clock.hour.value, clock.minute.value / 5 * 5);
+
+            assert_eq!(decode(clock, Format::Hour12), Ok(time));
+        }
+
+        let midnight = Clock {
+            midnight: Midnight::TwentyFour,
+            ..Clock::new(0.try_into().unwrap(), 0.try_into().unwrap(), Format::Hour24)
+        };
+        assert_eq!(decode(midnight, Format::Hour24), Ok((0, 0)));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn decode_invalid() {
+        let decode = |morse: &str| decode(morse.parse::<SymbolSequence>().unwrap(), Format::Hour12);
+
+        assert_eq!(decode("--.=----"), Ok((4, 45)));
+        assert_eq!(decode("--.=---- =="), Ok((4, 45)));
+
+        for morse in [
+            "",
+            "--.",
+            "--.=",
+            "--.----",
+            "--. ----",
+            "--.==----",
+            "=--.=----",
+            "--.=----=-",
+            "--.-=----",
+            ".=----",
+            "--...=----",
+            "-----=-",
+            "--.=-----",
+            "--.=-...",
+            "--=-A=-",
+            "A--=-",
+        ] {
+            assert_eq!(decode(morse), Err(Error::DecodeError), "{:?}", morse);
+        }
+    }
+
+    #[test]
+    fn without_alloc() {
+        let clock = Clock::new(
+            16.try_into().unwrap(),
+            47.try_into().unwrap(),
+            Format::Hour12,
+        );
+        let mut symbols = [Symbol::Short; 8];
+        let mut len = 0;
+
+        for (slot, symbol) in symbols.iter_mut().zip(clock) {
+            *slot = symbol;
+            len += 1;
+        }
+
+        let mut morse = ['\0'; 8];
+        for (slot, c) in morse.iter_mut().zip(symbols[..len].iter().copied().morse()) {
+            *slot = c;
+        }
+
+        assert_eq!(morse, ['-', '-', '.', '=', '-', '-', '-', '-']);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn midnight() {
+        let clock = |hour: u32, midnight| -> String {
+            Clock {
+                midnight,
+                ..Clock::new(
+                    hour.try_into().unwrap(),
+                    30.try_into().unwrap(),
+                    Format::Hour24,
+                )
+            }
+            .into_iter()
+            .morse()
+            .collect()
+        };
+
+        assert_eq!(clock(0, Midnight::Zero), "-=---");
+        assert_eq!(clock(0, Midnight::TwentyFour), "---------=---");
+        assert_eq!(clock(1, Midnight::TwentyFour), "-.=---");
+        assert_eq!(clock(12, Midnight::TwentyFour), "-----=---");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn meridiem() {
+        let clock = |hour: u32, format| Clock {
+            meridiem: true,
+            ..Clock::new(hour.try_into().unwrap(), 15.try_into().unwrap(), format)
+        };
+
+        let symbols: Vec<_> = clock(3, Format::Hour12).into_iter().collect();
+        assert_eq!(symbols.last(), Some(&Symbol::Meridiem(Meridiem::Am)));
+        assert_eq!(
+            symbols
+                .iter()
+                .filter(|s| matches!(s, Symbol::Meridiem(_)))
+                .count(),
+            1
+        );
+
+        let time: String = clock(15, Format::Hour12).into_iter().morse().collect();
+        assert_eq!(time, "--=--P");
+        assert_eq!(time.parse::<SymbolSequence>().unwrap().0.len(), 6);
+
+        let time: String = clock(0, Format::Hour12).into_iter().morse().collect();
+        assert_eq!(time, "-=--A");
+        let time: String = clock(12, Format::Hour12).into_iter().morse().collect();
+        assert_eq!(time, "-=--P");
+
+        // never in 24 hour mode
+        for hour in [3, 15] {
+            assert!(!clock(hour, Format::Hour24)
+                .into_iter()
+                .any(|s| matches!(s, Symbol::Meridiem(_))));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn midnight_hour12() {
+        let clock = Clock {
+            midnight: Midnight::TwentyFour,
+            ..Clock::new(
+                0.try_into().unwrap(),
+                30.try_into().unwrap(),
+                Format::Hour12,
+            )
+        };
+        let time: String = clock.into_iter().morse().collect();
+
+        assert_eq!(time, "-=---");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn symbol_sequence() {
+        let parsed: SymbolSequence = "...-".parse().unwrap();
+        let collected: SymbolSequence = [Symbol::Short, Symbol::Short, Symbol::Short, Symbol::Long]
+            .into_iter()
+            .collect();
+
+        assert_eq!(parsed, collected);
+        assert_eq!((&parsed).into_iter().morse().collect::<String>(), "...-");
+
+        let clock = Clock::new(
+            16.try_into().unwrap(),
+            47.try_into().unwrap(),
+            Format::Hour12,
+        );
+        let sequence: SymbolSequence = clock.into_iter().collect();
+        let time: String = sequence.clone().into_iter().morse().collect();
+
+        assert_eq!(time.parse::<SymbolSequence>().unwrap(), sequence);
+        assert_eq!(
+            "".parse::<SymbolSequence>().unwrap(),
+            SymbolSequence::default()
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn break_kinds() {
+        let symbols = [
+            Symbol::Short,
+            Symbol::Break(BreakKind::Symbol),
+            Symbol::Long,
+            Symbol::Break(BreakKind::Digit),
+            Symbol::Long,
+            Symbol::Break(BreakKind::Group),
+            Symbol::Short,
+        ];
+        let morse: String = symbols.into_iter().morse().collect();
+
+        assert_eq!(morse, ".- -=.");
+        assert_eq!(
+            morse.parse::<SymbolSequence>().unwrap().0,
+            symbols
+                .into_iter()
+                .filter(|&symbol| symbol != Symbol::Break(BreakKind::Symbol))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn break_kinds_eleven_eleven() {
+        let breaks = |symbols: Vec<Symbol>| -> Vec<_> {
+            symbols
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, symbol)| match symbol {
+                    Symbol::Break(kind) => Some((i, kind)),
+                    _ => None,
+                })
+                .collect()
+        };
+        let hour = 11.try_into().unwrap();
+        let minute = 11.try_into().unwrap();
+
+        let hands: Vec<_> = Clock::new(hour, minute, Format::Hour24)
+            .into_iter()
+            .collect();
+        assert_eq!(
+            hands.iter().copied().morse().collect::<String>(),
+            "----..=-.."
+        );
+        assert_eq!(breaks(hands), [(6, BreakKind::Group)]);
+
+        let one = [
+            Symbol::Short,
+            Symbol::Break(BreakKind::Symbol),
+            Symbol::Long,
+        ];
+        let table = vec![one; 10];
+        let digits: Vec<_> = Clock::builder(hour, minute, Format::Hour24)
+            .digits(&table)
+            .unwrap()
+            .build()
+            .collect();
+        assert_eq!(
+            digits.iter().copied().morse().collect::<String>(),
+            ".- .-=.- .-"
+        );
+        assert_eq!(
+            breaks(digits),
+            [
+                (1, BreakKind::Symbol),
+                (3, BreakKind::Digit),
+                (5, BreakKind::Symbol),
+                (7, BreakKind::Group),
+                (9, BreakKind::Symbol),
+                (11, BreakKind::Digit),
+                (13, BreakKind::Symbol),
+            ]
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn symbol_tokens() {
+        let symbols = [
+            Symbol::Short,
+            Symbol::Long,
+            Symbol::Break(BreakKind::Symbol),
+            Symbol::Break(BreakKind::Digit),
+            Symbol::Break(BreakKind::Group),
+            Symbol::Meridiem(Meridiem::Am),
+            Symbol::Meridiem(Meridiem::Pm),
+        ];
+
+        for symbol in symbols {
+            assert_eq!(symbol.to_string().parse(), Ok(symbol));
+        }
+
+        assert_eq!(Symbol::Break(BreakKind::Group).to_string(), "B");
+        for token in ["", "s", "X", "B ", "BB"] {
+            assert_eq!(token.parse::<Symbol>(), Err(Error::InvalidToken));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn format_tokens() {
+        for format in [Format::Hour12, Format::Hour24] {
+            assert_eq!(format.to_string().parse(), Ok(format));
+            assert_eq!(format!("{:?}", format).parse(), Ok(format));
+        }
+
+        assert_eq!(Format::Hour24.to_string(), "24");
+        for token in ["", "13", "hour12", "Hour 24"] {
+            assert_eq!(token.parse::<Format>(), Err(Error::InvalidFormat));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn morse_glyphs() {
+        let clock = Clock::new(
+            10.try_into().unwrap(),
+            8.try_into().unwrap(),
+            Format::Hour24,
+        );
+
+        let default: String = clock
+            .into_iter()
+            .morse_with(MorseGlyphs::default())
+            .collect();
+        assert_eq!(default, "----.=-.");
+        assert_eq!(default, clock.into_iter().morse().collect::<String>());
+
+        let syllables = MorseGlyphs {
+            short: "di",
+            long: "dah",
+            group_break: " / ",
+            ..MorseGlyphs::default()
+        };
+        let pieces: Vec<_> = clock.into_iter().morse_with(syllables).collect();
+        assert_eq!(pieces.concat(), "dahdahdahdahdi / dahdi");
+        assert_eq!(pieces.len(), clock.into_iter().count());
+
+        let symbols = [
+            Symbol::Short,
+            Symbol::Break(BreakKind::Symbol),
+            Symbol::Long,
+        ];
+        let pieces: Vec<_> = symbols.into_iter().morse_with(syllables).collect();
+        assert_eq!(pieces, ["di", "dah"]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn braille() {
+        let symbols = [
+            Symbol::Short,
+            Symbol::Break(BreakKind::Symbol),
+            Symbol::Long,
+            Symbol::Break(BreakKind::Digit),
+            Symbol::Long,
+            Symbol::Break(BreakKind::Group),
+            Symbol::Short,
+            Symbol::Meridiem(Meridiem::Pm),
+        ];
+        let braille = symbols.into_iter().braille();
+
+        assert_eq!(braille, "\u{2801}\u{2809}\u{2800}\u{2809}\u{2800}\u{2801}");
+        assert_eq!(braille, "⠁⠉⠀⠉⠀⠁");
+        // every cell is a single character, three bytes long in UTF-8
+        assert!(braille
+            .chars()
+            .all(|c| ('\u{2800}'..='\u{28ff}').contains(&c)));
+        assert_eq!(braille.len(), 3 * braille.chars().count());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn cw_text() {
+        let sequence: SymbolSequence = "=.-- -..=-.-==".parse().unwrap();
+        assert_eq!(sequence.into_iter().cw_text(), ".-- -.. / -.-");
+
+        let symbols = [
+            Symbol::Short,
+            Symbol::Break(BreakKind::Symbol),
+            Symbol::Short,
+            Symbol::Break(BreakKind::Digit),
+            Symbol::Break(BreakKind::Group),
+            Symbol::Long,
+        ];
+        assert_eq!(symbols.into_iter().cw_text(), ".. / -");
+        assert_eq!(iter::empty().cw_text(), "");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn invalid_symbol_sequence() {
+        assert_eq!(
+            "..x-".parse::<SymbolSequence>(),
+            Err(Error::InvalidSymbol('x'))
+        );
+        assert_eq!(Symbol::try_from('_'), Err(Error::InvalidSymbol('_')));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn all_times_count() {
+        assert_eq!(all_times(Format::Hour12).count(), 720);
+        assert_eq!(all_times(Format::Hour24).count(), 1440);
+
+        let last = all_times(Format::Hour24).last().unwrap();
+        let time: String = last.into_iter().morse().collect();
+        assert_eq!(time, "--------..=----..");
+    }
+
+    #[test]
+    fn distance() {
+        let clock = |hour: u32, minute: u32| {
+            Clock::new(
+                hour.try_into().unwrap(),
+                minute.try_into().unwrap(),
+                Format::Hour12,
+            )
+        };
+
+        // the same hand positions
+        assert_eq!(stream_distance(&clock(16, 47), &clock(16, 48)), 0);
+        // "--.=----" and "--..=----"
+        assert_eq!(stream_distance(&clock(16, 47), &clock(17, 47)), 3);
+        // "-=-" and "----..=----.."
+        assert_eq!(stream_distance(&clock(0, 0), &clock(11, 59)), 11);
+
+        for (a, b) in [((1, 2), (3, 4)), ((10, 55), (2, 0))] {
+            assert_eq!(
+                stream_distance(&clock(a.0, a.1), &clock(b.0, b.1)),
+                stream_distance(&clock(b.0, b.1), &clock(a.0, a.1)),
+            );
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn rle_round_trip() {
+        for (hour, minute) in [(0, 0), (16, 47), (23, 59)] {
+            let clock = Clock::new(
+                hour.try_into().unwrap(),
+                minute.try_into().unwrap(),
+                Format::Hour24,
+            );
+            let symbols: Vec<_> = clock.into_iter().collect();
+            let runs = rle(symbols.iter().copied());
+
+            assert_eq!(expand(&runs).collect::<Vec<_>>(), symbols);
+        }
+
+        assert_eq!(rle(iter::empty()), []);
+        assert_eq!(expand(&[]).count(), 0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn rle_runs() {
+        let clock = Clock::new(
+            23.try_into().unwrap(),
+            59.try_into().unwrap(),
+            Format::Hour12,
+        );
+
+        assert_eq!(
+            rle(clock.into_iter()),
+            [
+                (Symbol::Long, 4),
+                (Symbol::Short, 2),
+                (Symbol::Break(BreakKind::Group), 1),
+                (Symbol::Long, 4),
+                (Symbol::Short, 2),
+            ]
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn rle_long_run() {
+        let symbols = iter::repeat_n(Symbol::Short, u16::MAX as usize + 2);
+        let runs = rle(symbols);
+
+        assert_eq!(runs, [(Symbol::Short, u16::MAX), (Symbol::Short, 2)]);
+        assert_eq!(expand(&runs).count(), u16::MAX as usize + 2);
+    }
+
+    // The encoding layer relies on symbols being small and cheap to copy around. The
+    // payloads of `Break` and `Meridiem` both occupy the first byte and rustc only
+    // stores the variant in the unused values of a single payload, so the variant
+    // takes a second byte.
+    const _: () = assert!(core::mem::size_of::<Symbol>() <= 2);
+
+    const fn assert_copy_send_sync<T: Copy + Send + Sync>() {}
+    const _: () = assert_copy_send_sync::<Symbol>();
+
+    #[test]
+    fn clock_equality() {
+        let clock = |hour: u32, minute: u32, format| {
+            Clock::new(hour.try_into().unwrap(), minute.try_into().unwrap(), format)
+        };
+
+        assert_eq!(clock(16, 47, Format::Hour12), clock(16, 47, Format::Hour12));
+        // the minute hand only moves every 5 minutes
+        assert_eq!(clock(16, 47, Format::Hour12), clock(16, 49, Format::Hour12));
+        assert_ne!(clock(16, 47, Format::Hour12), clock(16, 50, Format::Hour12));
+        assert_ne!(clock(16, 47, Format::Hour12), clock(16, 47, Format::Hour24));
     }
 
     #[test]
     fn hand_equality() {
         assert_eq!(Hand::<Minute>::try_from(0), Hand::<Minute>::try_from(4));
     }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn schedule_01_01() {
+        let clock = Clock::new(1.try_into().unwrap(), 1.try_into().unwrap(), Format::Hour24);
+        let params = TimingParams::new(Duration::from_millis(500), 0.2, 0.6, 2.0).unwrap();
+        let ms = Duration::from_millis;
+
+        // "-.=-", the off time of the short is merged with the break
+        assert_eq!(
+            schedule(clock, &params),
+            [
+                (LedState::On, ms(300)),
+                (LedState::Off, ms(200)),
+                (LedState::On, ms(100)),
+                (LedState::Off, ms(1400)),
+                (LedState::On, ms(300)),
+                (LedState::Off, ms(200)),
+            ]
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn schedule_max_base() {
+        let clock = Clock::new(4.try_into().unwrap(), 2.try_into().unwrap(), Format::Hour24);
+        let params = TimingParams::new(Duration::MAX, 0.5, 0.5, 1.0).unwrap();
+
+        let (_, digit) = params.durations(Symbol::Break(BreakKind::Digit));
+        assert!(digit > Duration::MAX / 3 && digit < Duration::MAX / 2);
+        // "-.=.."
+        assert_eq!(schedule(clock, &params).len(), 8);
+        assert!(TimingParams::new(Duration::MAX, 0.5, 0.5, 1.5).is_err());
+    }
+
+    #[test]
+    fn morse_timed_invalid() {
+        let base = Duration::from_millis(500);
+        let timed = |short_duty, long_duty, break_factor| {
+            iter::empty()
+                .morse_timed(base, short_duty, long_duty, break_factor)
+                .err()
+        };
+
+        assert_eq!(timed(0.0, 1.0, 0.0), None);
+        assert_eq!(timed(-0.1, 0.6, 1.0), Some(Error::InvalidTiming));
+        assert_eq!(timed(0.2, 1.1, 1.0), Some(Error::InvalidTiming));
+        assert_eq!(timed(f64::NAN, 0.6, 1.0), Some(Error::InvalidTiming));
+        assert_eq!(timed(0.2, 0.6, -1.0), Some(Error::InvalidTiming));
+        assert_eq!(timed(0.2, 0.6, f64::INFINITY), Some(Error::InvalidTiming));
+    }
 }